@@ -0,0 +1,227 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+
+use crate::error::ContractError;
+use crate::state::{read_config, store_config, Config};
+
+use moneymarket::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
+use moneymarket::distribution_model::{
+    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let config = Config {
+        owner: deps.api.addr_canonicalize(&msg.owner)?,
+        emission_cap: msg.emission_cap,
+        emission_floor: msg.emission_floor,
+        increment_multiplier: msg.increment_multiplier,
+        decrement_multiplier: msg.decrement_multiplier,
+    };
+    assert_valid_config(&config)?;
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            emission_cap,
+            emission_floor,
+            increment_multiplier,
+            decrement_multiplier,
+        } => update_config(
+            deps,
+            info,
+            emission_cap,
+            emission_floor,
+            increment_multiplier,
+            decrement_multiplier,
+        ),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner)?;
+            Ok(propose_new_owner(
+                deps,
+                &env,
+                &info,
+                &owner_addr,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, &env, &info, store_owner)?),
+        ExecuteMsg::RejectOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner)?;
+            Ok(drop_ownership_proposal(deps, &info, &owner_addr)?)
+        }
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    emission_cap: Option<Decimal256>,
+    emission_floor: Option<Decimal256>,
+    increment_multiplier: Option<Decimal256>,
+    decrement_multiplier: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(emission_cap) = emission_cap {
+        config.emission_cap = emission_cap;
+    }
+
+    if let Some(emission_floor) = emission_floor {
+        config.emission_floor = emission_floor;
+    }
+
+    if let Some(increment_multiplier) = increment_multiplier {
+        config.increment_multiplier = increment_multiplier;
+    }
+
+    if let Some(decrement_multiplier) = decrement_multiplier {
+        config.decrement_multiplier = decrement_multiplier;
+    }
+
+    assert_valid_config(&config)?;
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+fn store_owner(deps: DepsMut, owner: Addr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = deps.api.addr_canonicalize(owner.as_str())?;
+    store_config(deps.storage, &config)
+}
+
+/// The multipliers have to move the rate toward the band, and a zero
+/// floor could never be multiplied back up, otherwise the emission
+/// would never settle
+fn assert_valid_config(config: &Config) -> Result<(), ContractError> {
+    if config.emission_floor.is_zero() {
+        return Err(ContractError::InvalidEmissionFloor {});
+    }
+
+    if config.emission_floor > config.emission_cap {
+        return Err(ContractError::InvalidEmissionBounds {
+            emission_cap: config.emission_cap,
+            emission_floor: config.emission_floor,
+        });
+    }
+
+    if config.increment_multiplier <= Decimal256::one() {
+        return Err(ContractError::InvalidIncrementMultiplier {
+            increment_multiplier: config.increment_multiplier,
+        });
+    }
+
+    if config.decrement_multiplier >= Decimal256::one() {
+        return Err(ContractError::InvalidDecrementMultiplier {
+            decrement_multiplier: config.decrement_multiplier,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::AncEmissionRate {
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+        } => to_binary(&query_anc_emission_rate(
+            deps,
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+        )?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        emission_cap: config.emission_cap,
+        emission_floor: config.emission_floor,
+        increment_multiplier: config.increment_multiplier,
+        decrement_multiplier: config.decrement_multiplier,
+    })
+}
+
+pub fn query_anc_emission_rate(
+    deps: Deps,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    current_emission_rate: Decimal256,
+) -> StdResult<AncEmissionRateResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    Ok(AncEmissionRateResponse {
+        emission_rate: compute_emission_rate(
+            &config,
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+        ),
+    })
+}
+
+/// Next emission rate; emission is increased while the deposit rate is
+/// below the threshold, decreased while it is above the target, and
+/// always kept between the floor and the cap
+pub fn compute_emission_rate(
+    config: &Config,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    current_emission_rate: Decimal256,
+) -> Decimal256 {
+    let emission_rate = if deposit_rate < threshold_deposit_rate {
+        current_emission_rate * config.increment_multiplier
+    } else if deposit_rate > target_deposit_rate {
+        current_emission_rate * config.decrement_multiplier
+    } else {
+        current_emission_rate
+    };
+
+    if emission_rate > config.emission_cap {
+        config.emission_cap
+    } else if emission_rate < config.emission_floor {
+        config.emission_floor
+    } else {
+        emission_rate
+    }
+}
@@ -0,0 +1,31 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::StdError;
+use moneymarket::common::OwnershipError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Emission floor {emission_floor} must not exceed emission cap {emission_cap}")]
+    InvalidEmissionBounds {
+        emission_cap: Decimal256,
+        emission_floor: Decimal256,
+    },
+
+    #[error("Emission floor must be greater than 0")]
+    InvalidEmissionFloor {},
+
+    #[error("Increment multiplier must be greater than 1: {increment_multiplier}")]
+    InvalidIncrementMultiplier { increment_multiplier: Decimal256 },
+
+    #[error("Decrement multiplier must be less than 1: {decrement_multiplier}")]
+    InvalidDecrementMultiplier { decrement_multiplier: Decimal256 },
+}
@@ -0,0 +1,291 @@
+use crate::contract::{compute_emission_rate, execute, instantiate, query};
+use crate::error::ContractError;
+use crate::state::Config;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, CanonicalAddr};
+use moneymarket::distribution_model::{
+    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: "owner0000".to_string(),
+        emission_cap: Decimal256::from_uint256(100u64),
+        emission_floor: Decimal256::from_uint256(10u64),
+        increment_multiplier: Decimal256::percent(110),
+        decrement_multiplier: Decimal256::percent(90),
+    }
+}
+
+/// xorshift64, to generate reproducible cases without extra dependencies
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn config(&mut self) -> Config {
+        let emission_floor = Decimal256::from_uint256(1 + self.below(100));
+        Config {
+            owner: CanonicalAddr::from(vec![]),
+            emission_cap: emission_floor + Decimal256::from_uint256(self.below(1000)),
+            emission_floor,
+            increment_multiplier: Decimal256::one() + Decimal256::permille(10 + self.below(490)),
+            decrement_multiplier: Decimal256::one() - Decimal256::permille(10 + self.below(490)),
+        }
+    }
+}
+
+const THRESHOLD_DEPOSIT_RATE: u64 = 3;
+const TARGET_DEPOSIT_RATE: u64 = 5;
+
+fn next_rate(config: &Config, deposit_rate: u64, current_emission_rate: Decimal256) -> Decimal256 {
+    compute_emission_rate(
+        config,
+        Decimal256::permille(deposit_rate),
+        Decimal256::permille(TARGET_DEPOSIT_RATE),
+        Decimal256::permille(THRESHOLD_DEPOSIT_RATE),
+        current_emission_rate,
+    )
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg();
+    msg.emission_floor = Decimal256::from_uint256(101u64);
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidEmissionBounds { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut msg = instantiate_msg();
+    msg.emission_floor = Decimal256::zero();
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidEmissionFloor {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut msg = instantiate_msg();
+    msg.increment_multiplier = Decimal256::one();
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidIncrementMultiplier {
+            increment_multiplier,
+        }) => assert_eq!(increment_multiplier, Decimal256::one()),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut msg = instantiate_msg();
+    msg.decrement_multiplier = Decimal256::one();
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidDecrementMultiplier {
+            decrement_multiplier,
+        }) => assert_eq!(decrement_multiplier, Decimal256::one()),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut msg = instantiate_msg();
+    msg.increment_multiplier = Decimal256::percent(99);
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidIncrementMultiplier {
+            increment_multiplier,
+        }) => assert_eq!(increment_multiplier, Decimal256::percent(99)),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut msg = instantiate_msg();
+    msg.decrement_multiplier = Decimal256::percent(101);
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidDecrementMultiplier {
+            decrement_multiplier,
+        }) => assert_eq!(decrement_multiplier, Decimal256::percent(101)),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            emission_cap: Decimal256::from_uint256(100u64),
+            emission_floor: Decimal256::from_uint256(10u64),
+            increment_multiplier: Decimal256::percent(110),
+            decrement_multiplier: Decimal256::percent(90),
+        }
+    );
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        emission_cap: Some(Decimal256::from_uint256(200u64)),
+        emission_floor: None,
+        increment_multiplier: None,
+        decrement_multiplier: Some(Decimal256::percent(80)),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the config is validated as a whole
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            emission_cap: Some(Decimal256::from_uint256(5u64)),
+            emission_floor: None,
+            increment_multiplier: None,
+            decrement_multiplier: None,
+        },
+    );
+    match res {
+        Err(ContractError::InvalidEmissionBounds { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.emission_cap, Decimal256::from_uint256(200u64));
+    assert_eq!(config_res.decrement_multiplier, Decimal256::percent(80));
+}
+
+#[test]
+fn anc_emission_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    let query_rate = |deposit_rate: u64, current_emission_rate: u64| -> Decimal256 {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AncEmissionRate {
+                deposit_rate: Decimal256::permille(deposit_rate),
+                target_deposit_rate: Decimal256::permille(TARGET_DEPOSIT_RATE),
+                threshold_deposit_rate: Decimal256::permille(THRESHOLD_DEPOSIT_RATE),
+                current_emission_rate: Decimal256::from_uint256(current_emission_rate),
+            },
+        )
+        .unwrap();
+        let rate_res: AncEmissionRateResponse = from_binary(&res).unwrap();
+        rate_res.emission_rate
+    };
+
+    // below the threshold, 50 * 1.1
+    assert_eq!(query_rate(2, 50), Decimal256::from_uint256(55u64));
+    // within the band
+    assert_eq!(query_rate(4, 50), Decimal256::from_uint256(50u64));
+    // above the target, 50 * 0.9
+    assert_eq!(query_rate(6, 50), Decimal256::from_uint256(45u64));
+
+    // clamped to the cap and the floor
+    assert_eq!(query_rate(2, 95), Decimal256::from_uint256(100u64));
+    assert_eq!(query_rate(6, 11), Decimal256::from_uint256(10u64));
+    assert_eq!(query_rate(4, 1000), Decimal256::from_uint256(100u64));
+}
+
+#[test]
+fn emission_rate_stays_within_bounds() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..200 {
+        let config = rng.config();
+        let mut emission_rate = Decimal256::from_uint256(rng.below(10000));
+
+        for _ in 0..100 {
+            emission_rate = next_rate(&config, rng.below(8), emission_rate);
+            assert!(emission_rate >= config.emission_floor, "{:?}", config);
+            assert!(emission_rate <= config.emission_cap, "{:?}", config);
+        }
+    }
+}
+
+#[test]
+fn emission_rate_converges() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..200 {
+        let config = rng.config();
+        let start = Decimal256::from_uint256(rng.below(10000));
+
+        // within the band the rate only moves into the bounds
+        let settled = next_rate(&config, 4, start);
+        assert_eq!(next_rate(&config, 4, settled), settled);
+
+        // below the threshold it increases monotonically up to the cap
+        let mut emission_rate = settled;
+        let mut steps = 0;
+        while emission_rate != config.emission_cap {
+            let next = next_rate(&config, 2, emission_rate);
+            assert!(next > emission_rate, "{:?}", config);
+            emission_rate = next;
+            steps += 1;
+            assert!(steps < 1000, "{:?}", config);
+        }
+        assert_eq!(next_rate(&config, 2, emission_rate), config.emission_cap);
+
+        // above the target it decreases monotonically down to the floor
+        let mut steps = 0;
+        while emission_rate != config.emission_floor {
+            let next = next_rate(&config, 6, emission_rate);
+            assert!(next < emission_rate, "{:?}", config);
+            emission_rate = next;
+            steps += 1;
+            assert!(steps < 1000, "{:?}", config);
+        }
+        assert_eq!(next_rate(&config, 6, emission_rate), config.emission_floor);
+    }
+}
@@ -0,0 +1,210 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+
+use crate::error::ContractError;
+use crate::state::{read_config, store_config, Config};
+
+use moneymarket::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
+use moneymarket::interest_model::{
+    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let optimal_utilization = msg.optimal_utilization.unwrap_or_else(Decimal256::one);
+    assert_optimal_utilization_range(optimal_utilization)?;
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_canonicalize(&msg.owner)?,
+            base_rate: msg.base_rate,
+            interest_multiplier: msg.interest_multiplier,
+            optimal_utilization,
+            jump_multiplier: msg.jump_multiplier.unwrap_or(msg.interest_multiplier),
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            base_rate,
+            interest_multiplier,
+            optimal_utilization,
+            jump_multiplier,
+        } => update_config(
+            deps,
+            info,
+            base_rate,
+            interest_multiplier,
+            optimal_utilization,
+            jump_multiplier,
+        ),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner)?;
+            Ok(propose_new_owner(
+                deps,
+                &env,
+                &info,
+                &owner_addr,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, &env, &info, store_owner)?),
+        ExecuteMsg::RejectOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner)?;
+            Ok(drop_ownership_proposal(deps, &info, &owner_addr)?)
+        }
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    base_rate: Option<Decimal256>,
+    interest_multiplier: Option<Decimal256>,
+    optimal_utilization: Option<Decimal256>,
+    jump_multiplier: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(base_rate) = base_rate {
+        config.base_rate = base_rate;
+    }
+
+    if let Some(interest_multiplier) = interest_multiplier {
+        config.interest_multiplier = interest_multiplier;
+    }
+
+    if let Some(optimal_utilization) = optimal_utilization {
+        assert_optimal_utilization_range(optimal_utilization)?;
+        config.optimal_utilization = optimal_utilization;
+    }
+
+    if let Some(jump_multiplier) = jump_multiplier {
+        config.jump_multiplier = jump_multiplier;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+fn store_owner(deps: DepsMut, owner: Addr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = deps.api.addr_canonicalize(owner.as_str())?;
+    store_config(deps.storage, &config)
+}
+
+fn assert_optimal_utilization_range(optimal_utilization: Decimal256) -> Result<(), ContractError> {
+    if optimal_utilization.is_zero() || optimal_utilization > Decimal256::one() {
+        return Err(ContractError::InvalidOptimalUtilization {
+            optimal_utilization,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::BorrowRate {
+            market_balance,
+            total_liabilities,
+            total_reserves,
+        } => to_binary(&query_borrow_rate(
+            deps,
+            market_balance,
+            total_liabilities,
+            total_reserves,
+        )?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        base_rate: config.base_rate,
+        interest_multiplier: config.interest_multiplier,
+        optimal_utilization: config.optimal_utilization,
+        jump_multiplier: config.jump_multiplier,
+    })
+}
+
+pub fn query_borrow_rate(
+    deps: Deps,
+    market_balance: Uint256,
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+) -> StdResult<BorrowRateResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let utilization_ratio =
+        compute_utilization_ratio(market_balance, total_liabilities, total_reserves);
+
+    Ok(BorrowRateResponse {
+        rate: compute_borrow_rate(&config, utilization_ratio),
+    })
+}
+
+/// Share of the market deposits which is borrowed, capped to one
+/// when the reserves exceed the market balance
+pub fn compute_utilization_ratio(
+    market_balance: Uint256,
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+) -> Decimal256 {
+    if total_liabilities.is_zero() {
+        return Decimal256::zero();
+    }
+
+    let total_value_in_market = Decimal256::from_uint256(market_balance) + total_liabilities;
+    if total_value_in_market <= total_reserves {
+        return Decimal256::one();
+    }
+
+    std::cmp::min(
+        total_liabilities / (total_value_in_market - total_reserves),
+        Decimal256::one(),
+    )
+}
+
+/// Borrow rate per block; continuous at the kink,
+/// and linear when `optimal_utilization` is one
+pub fn compute_borrow_rate(config: &Config, utilization_ratio: Decimal256) -> Decimal256 {
+    if utilization_ratio <= config.optimal_utilization {
+        return config.base_rate + utilization_ratio * config.interest_multiplier;
+    }
+
+    config.base_rate
+        + config.optimal_utilization * config.interest_multiplier
+        + (utilization_ratio - config.optimal_utilization) * config.jump_multiplier
+}
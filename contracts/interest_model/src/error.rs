@@ -0,0 +1,19 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::StdError;
+use moneymarket::common::OwnershipError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Optimal utilization must be greater than 0 and at most 1: {optimal_utilization}")]
+    InvalidOptimalUtilization { optimal_utilization: Decimal256 },
+}
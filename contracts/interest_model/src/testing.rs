@@ -0,0 +1,199 @@
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::from_binary;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use moneymarket::interest_model::{
+    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(1),
+        interest_multiplier: Decimal256::percent(2),
+        optimal_utilization: None,
+        jump_multiplier: None,
+    }
+}
+
+fn query_rate(
+    deps: cosmwasm_std::Deps,
+    market_balance: u64,
+    total_liabilities: u64,
+    total_reserves: u64,
+) -> Decimal256 {
+    let res = query(
+        deps,
+        mock_env(),
+        QueryMsg::BorrowRate {
+            market_balance: Uint256::from(market_balance),
+            total_liabilities: Decimal256::from_uint256(total_liabilities),
+            total_reserves: Decimal256::from_uint256(total_reserves),
+        },
+    )
+    .unwrap();
+    let rate_res: BorrowRateResponse = from_binary(&res).unwrap();
+    rate_res.rate
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg();
+    msg.optimal_utilization = Some(Decimal256::zero());
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidOptimalUtilization { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    // the linear model is the default
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            base_rate: Decimal256::percent(1),
+            interest_multiplier: Decimal256::percent(2),
+            optimal_utilization: Decimal256::one(),
+            jump_multiplier: Decimal256::percent(2),
+        }
+    );
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        base_rate: Some(Decimal256::percent(2)),
+        interest_multiplier: None,
+        optimal_utilization: Some(Decimal256::percent(80)),
+        jump_multiplier: Some(Decimal256::percent(50)),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            base_rate: None,
+            interest_multiplier: None,
+            optimal_utilization: Some(Decimal256::percent(101)),
+            jump_multiplier: None,
+        },
+    );
+    match res {
+        Err(ContractError::InvalidOptimalUtilization {
+            optimal_utilization,
+        }) => assert_eq!(optimal_utilization, Decimal256::percent(101)),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            base_rate: Decimal256::percent(2),
+            interest_multiplier: Decimal256::percent(2),
+            optimal_utilization: Decimal256::percent(80),
+            jump_multiplier: Decimal256::percent(50),
+        }
+    );
+}
+
+#[test]
+fn linear_borrow_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    // nothing borrowed
+    assert_eq!(
+        query_rate(deps.as_ref(), 1000000, 0, 0),
+        Decimal256::percent(1)
+    );
+
+    // utilization = 500000 / (1000000 + 500000 - 500000) = 0.5
+    // rate = 0.01 + 0.5 * 0.02 = 0.02
+    assert_eq!(
+        query_rate(deps.as_ref(), 1000000, 500000, 500000),
+        Decimal256::percent(2)
+    );
+
+    // reserves exceeding the balance cap the utilization to one
+    assert_eq!(
+        query_rate(deps.as_ref(), 100000, 500000, 1000000),
+        Decimal256::percent(3)
+    );
+}
+
+#[test]
+fn kinked_borrow_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg();
+    msg.optimal_utilization = Some(Decimal256::percent(80));
+    msg.jump_multiplier = Some(Decimal256::one());
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // below the kink the linear model applies
+    // rate = 0.01 + 0.5 * 0.02 = 0.02
+    assert_eq!(
+        query_rate(deps.as_ref(), 500000, 500000, 0),
+        Decimal256::percent(2)
+    );
+
+    // at the kink, rate = 0.01 + 0.8 * 0.02 = 0.026
+    assert_eq!(
+        query_rate(deps.as_ref(), 200000, 800000, 0),
+        Decimal256::permille(26)
+    );
+
+    // above the kink, rate = 0.026 + (0.9 - 0.8) * 1 = 0.126
+    assert_eq!(
+        query_rate(deps.as_ref(), 100000, 900000, 0),
+        Decimal256::permille(126)
+    );
+}
@@ -0,0 +1,60 @@
+use cosmwasm_bignumber::Decimal256;
+
+use crate::state::{Config, FedPrice};
+
+/// Aggregate the prices fed within the price window: the median of the
+/// prices which are within `max_deviation` of the median of all of them.
+/// The aggregate is as old as the oldest price it uses; None when fewer
+/// than `min_feeders` prices are left
+pub fn aggregate_price(
+    config: &Config,
+    fed_prices: &[FedPrice],
+    block_time: u64,
+) -> Option<FedPrice> {
+    let valid_update_time = block_time.saturating_sub(config.price_window);
+    let fresh: Vec<&FedPrice> = fed_prices
+        .iter()
+        .filter(|fed_price| fed_price.last_updated_time >= valid_update_time)
+        .collect();
+
+    let reference = median(fresh.iter().map(|fed_price| fed_price.price).collect())?;
+    let max_distance = reference * config.max_deviation;
+    let accepted: Vec<&FedPrice> = fresh
+        .into_iter()
+        .filter(|fed_price| distance(fed_price.price, reference) <= max_distance)
+        .collect();
+
+    if (accepted.len() as u64) < config.min_feeders {
+        return None;
+    }
+
+    Some(FedPrice {
+        price: median(accepted.iter().map(|fed_price| fed_price.price).collect())?,
+        last_updated_time: accepted
+            .iter()
+            .map(|fed_price| fed_price.last_updated_time)
+            .min()?,
+    })
+}
+
+fn median(mut prices: Vec<Decimal256>) -> Option<Decimal256> {
+    if prices.is_empty() {
+        return None;
+    }
+
+    prices.sort();
+    let mid = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        return Some(prices[mid]);
+    }
+
+    Some((prices[mid - 1] + prices[mid]) / Decimal256::from_uint256(2u64))
+}
+
+fn distance(a: Decimal256, b: Decimal256) -> Decimal256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
@@ -0,0 +1,344 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+
+use crate::aggregate::aggregate_price;
+use crate::error::ContractError;
+use crate::state::{
+    is_feeder, read_assets, read_config, read_fed_prices, read_feeders, remove_asset,
+    remove_feeder, store_asset, store_config, store_fed_price, store_feeder, Config, FedPrice,
+};
+
+use moneymarket::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
+use moneymarket::oracle::{
+    price_not_found, ConfigResponse, ExecuteMsg, FeederResponse, InstantiateMsg, PriceResponse,
+    PricesResponse, PricesResponseElem, QueryMsg,
+};
+
+/// Maximum number of feeders of an asset, to bound the aggregation
+pub const MAX_FEEDERS: usize = 16;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    assert_price_window(msg.price_window)?;
+    assert_max_deviation(msg.max_deviation)?;
+    assert_min_feeders(msg.min_feeders)?;
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_canonicalize(&msg.owner)?,
+            base_asset: msg.base_asset,
+            price_window: msg.price_window,
+            max_deviation: msg.max_deviation,
+            min_feeders: msg.min_feeders,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner)?;
+            Ok(propose_new_owner(
+                deps,
+                &env,
+                &info,
+                &owner_addr,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, &env, &info, store_owner)?),
+        ExecuteMsg::RejectOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner)?;
+            Ok(drop_ownership_proposal(deps, &info, &owner_addr)?)
+        }
+        ExecuteMsg::UpdateConfig {
+            price_window,
+            max_deviation,
+            min_feeders,
+        } => update_config(deps, info, price_window, max_deviation, min_feeders),
+        ExecuteMsg::RegisterFeeder { asset, feeder } => {
+            let api = deps.api;
+            register_feeder(deps, info, asset, api.addr_validate(&feeder)?)
+        }
+        ExecuteMsg::DeregisterFeeder { asset, feeder } => {
+            let api = deps.api;
+            deregister_feeder(deps, info, asset, api.addr_validate(&feeder)?)
+        }
+        ExecuteMsg::FeedPrice { prices } => feed_prices(deps, env, info, prices),
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    price_window: Option<u64>,
+    max_deviation: Option<Decimal256>,
+    min_feeders: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    assert_owner(deps.as_ref(), &config, &info)?;
+
+    if let Some(price_window) = price_window {
+        assert_price_window(price_window)?;
+        config.price_window = price_window;
+    }
+
+    if let Some(max_deviation) = max_deviation {
+        assert_max_deviation(max_deviation)?;
+        config.max_deviation = max_deviation;
+    }
+
+    if let Some(min_feeders) = min_feeders {
+        assert_min_feeders(min_feeders)?;
+        config.min_feeders = min_feeders;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+pub fn register_feeder(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: String,
+    feeder: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_owner(deps.as_ref(), &config, &info)?;
+
+    let feeder_raw = deps.api.addr_canonicalize(feeder.as_str())?;
+    if is_feeder(deps.storage, &asset, &feeder_raw)? {
+        return Err(ContractError::FeederAlreadyRegistered {
+            asset,
+            feeder: feeder.to_string(),
+        });
+    }
+
+    if read_feeders(deps.storage, &asset)?.len() >= MAX_FEEDERS {
+        return Err(ContractError::TooManyFeeders {
+            asset,
+            max: MAX_FEEDERS,
+        });
+    }
+
+    store_feeder(deps.storage, &asset, &feeder_raw)?;
+    store_asset(deps.storage, &asset)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_feeder"),
+        attr("asset", asset),
+        attr("feeder", feeder),
+    ]))
+}
+
+pub fn deregister_feeder(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: String,
+    feeder: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_owner(deps.as_ref(), &config, &info)?;
+
+    let feeder_raw = deps.api.addr_canonicalize(feeder.as_str())?;
+    if !is_feeder(deps.storage, &asset, &feeder_raw)? {
+        return Err(ContractError::FeederNotFound {
+            asset,
+            feeder: feeder.to_string(),
+        });
+    }
+
+    remove_feeder(deps.storage, &asset, &feeder_raw);
+    if read_feeders(deps.storage, &asset)?.is_empty() {
+        remove_asset(deps.storage, &asset);
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "deregister_feeder"),
+        attr("asset", asset),
+        attr("feeder", feeder),
+    ]))
+}
+
+/// Store the prices of the sender; it has to be a feeder of every asset
+pub fn feed_prices(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    prices: Vec<(String, Decimal256)>,
+) -> Result<Response, ContractError> {
+    let feeder_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let mut attributes = vec![attr("action", "feed_prices"), attr("feeder", &info.sender)];
+    for (asset, price) in prices {
+        if !is_feeder(deps.storage, &asset, &feeder_raw)? {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if price.is_zero() {
+            return Err(ContractError::InvalidPrice { asset });
+        }
+
+        store_fed_price(
+            deps.storage,
+            &asset,
+            &feeder_raw,
+            &FedPrice {
+                price,
+                last_updated_time: env.block.time.seconds(),
+            },
+        )?;
+
+        attributes.push(attr("asset", asset));
+        attributes.push(attr("price", price.to_string()));
+    }
+
+    Ok(Response::new().add_attributes(attributes))
+}
+
+fn store_owner(deps: DepsMut, owner: Addr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = deps.api.addr_canonicalize(owner.as_str())?;
+    store_config(deps.storage, &config)
+}
+
+fn assert_owner(deps: Deps, config: &Config, info: &MessageInfo) -> Result<(), ContractError> {
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+fn assert_price_window(price_window: u64) -> Result<(), ContractError> {
+    if price_window == 0 {
+        return Err(ContractError::InvalidPriceWindow {});
+    }
+
+    Ok(())
+}
+
+/// A zero deviation would only keep the prices equal to the median
+fn assert_max_deviation(max_deviation: Decimal256) -> Result<(), ContractError> {
+    if max_deviation.is_zero() || max_deviation > Decimal256::one() {
+        return Err(ContractError::InvalidMaxDeviation { max_deviation });
+    }
+
+    Ok(())
+}
+
+/// More than `MAX_FEEDERS` could never be met
+fn assert_min_feeders(min_feeders: u64) -> Result<(), ContractError> {
+    if min_feeders == 0 || min_feeders > MAX_FEEDERS as u64 {
+        return Err(ContractError::InvalidMinFeeders {
+            min_feeders,
+            max: MAX_FEEDERS,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Feeder { asset } => to_binary(&query_feeder(deps, asset)?),
+        QueryMsg::Price { base, quote } => to_binary(&query_price(deps, env, base, quote)?),
+        QueryMsg::Prices { start_after, limit } => {
+            to_binary(&query_prices(deps, env, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        base_asset: config.base_asset,
+        price_window: config.price_window,
+        max_deviation: config.max_deviation,
+        min_feeders: config.min_feeders,
+    })
+}
+
+pub fn query_feeder(deps: Deps, asset: String) -> StdResult<FeederResponse> {
+    let feeders = read_feeders(deps.storage, &asset)?
+        .iter()
+        .map(|feeder| Ok(deps.api.addr_humanize(feeder)?.to_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+
+    Ok(FeederResponse { asset, feeders })
+}
+
+pub fn query_price(deps: Deps, env: Env, base: String, quote: String) -> StdResult<PriceResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let base_price = read_price(deps, &env, &config, &base)?;
+    let quote_price = read_price(deps, &env, &config, &quote)?;
+
+    Ok(PriceResponse {
+        rate: base_price.price / quote_price.price,
+        last_updated_base: base_price.last_updated_time,
+        last_updated_quote: quote_price.last_updated_time,
+    })
+}
+
+/// Aggregated prices in the base asset; assets without a fresh price are left out
+pub fn query_prices(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PricesResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    let mut prices: Vec<PricesResponseElem> = vec![];
+    for asset in read_assets(deps.storage, start_after, limit)? {
+        let fed_prices = read_fed_prices(deps.storage, &asset)?;
+        if let Some(aggregate) = aggregate_price(&config, &fed_prices, env.block.time.seconds()) {
+            prices.push(PricesResponseElem {
+                asset,
+                price: aggregate.price,
+                last_updated_time: aggregate.last_updated_time,
+            });
+        }
+    }
+
+    Ok(PricesResponse { prices })
+}
+
+/// Price of an asset in the base asset; the base asset is never outdated
+fn read_price(deps: Deps, env: &Env, config: &Config, asset: &str) -> StdResult<FedPrice> {
+    if asset == config.base_asset {
+        return Ok(FedPrice {
+            price: Decimal256::one(),
+            last_updated_time: u64::MAX,
+        });
+    }
+
+    let fed_prices = read_fed_prices(deps.storage, asset)?;
+    aggregate_price(config, &fed_prices, env.block.time.seconds())
+        .ok_or_else(|| price_not_found(asset))
+}
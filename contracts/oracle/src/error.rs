@@ -0,0 +1,37 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::StdError;
+use moneymarket::common::OwnershipError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Price window must be greater than 0")]
+    InvalidPriceWindow {},
+
+    #[error("Max deviation must be greater than 0 and at most 1: {max_deviation}")]
+    InvalidMaxDeviation { max_deviation: Decimal256 },
+
+    #[error("Min feeders must be between 1 and {max}: {min_feeders}")]
+    InvalidMinFeeders { min_feeders: u64, max: usize },
+
+    #[error("{feeder} is already a feeder of {asset}")]
+    FeederAlreadyRegistered { asset: String, feeder: String },
+
+    #[error("{feeder} is not a feeder of {asset}")]
+    FeederNotFound { asset: String, feeder: String },
+
+    #[error("{asset} cannot have more than {max} feeders")]
+    TooManyFeeders { asset: String, max: usize },
+
+    #[error("Price of {asset} must be greater than 0")]
+    InvalidPrice { asset: String },
+}
@@ -0,0 +1,116 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+
+pub static KEY_CONFIG: &[u8] = b"config";
+pub static PREFIX_ASSET: &[u8] = b"asset";
+pub static PREFIX_FEEDER: &[u8] = b"feeder";
+pub static PREFIX_FED_PRICE: &[u8] = b"fed_price";
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: CanonicalAddr,
+    pub base_asset: String,
+    pub price_window: u64,
+    pub max_deviation: Decimal256,
+    pub min_feeders: u64,
+}
+
+/// Last price fed by a feeder of an asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FedPrice {
+    pub price: Decimal256,
+    pub last_updated_time: u64,
+}
+
+pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+/// Assets with at least one feeder, keyed by name for the paginated queries
+pub fn store_asset(storage: &mut dyn Storage, asset: &str) -> StdResult<()> {
+    Bucket::new(storage, PREFIX_ASSET).save(asset.as_bytes(), &asset.to_string())
+}
+
+pub fn remove_asset(storage: &mut dyn Storage, asset: &str) {
+    Bucket::<String>::new(storage, PREFIX_ASSET).remove(asset.as_bytes())
+}
+
+pub fn read_assets(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let asset_bucket: ReadonlyBucket<String> = ReadonlyBucket::new(storage, PREFIX_ASSET);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|asset| {
+        let mut v = asset.into_bytes();
+        v.push(1);
+        v
+    });
+
+    asset_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
+
+pub fn store_feeder(
+    storage: &mut dyn Storage,
+    asset: &str,
+    feeder: &CanonicalAddr,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_FEEDER, asset.as_bytes()]).save(feeder.as_slice(), &true)
+}
+
+pub fn remove_feeder(storage: &mut dyn Storage, asset: &str, feeder: &CanonicalAddr) {
+    Bucket::<bool>::multilevel(storage, &[PREFIX_FEEDER, asset.as_bytes()])
+        .remove(feeder.as_slice());
+    Bucket::<FedPrice>::multilevel(storage, &[PREFIX_FED_PRICE, asset.as_bytes()])
+        .remove(feeder.as_slice());
+}
+
+pub fn is_feeder(storage: &dyn Storage, asset: &str, feeder: &CanonicalAddr) -> StdResult<bool> {
+    let feeder: Option<bool> =
+        ReadonlyBucket::multilevel(storage, &[PREFIX_FEEDER, asset.as_bytes()])
+            .may_load(feeder.as_slice())?;
+    Ok(feeder.is_some())
+}
+
+pub fn read_feeders(storage: &dyn Storage, asset: &str) -> StdResult<Vec<CanonicalAddr>> {
+    ReadonlyBucket::<bool>::multilevel(storage, &[PREFIX_FEEDER, asset.as_bytes()])
+        .range(None, None, Order::Ascending)
+        .map(|elem| Ok(CanonicalAddr::from(elem?.0)))
+        .collect()
+}
+
+pub fn store_fed_price(
+    storage: &mut dyn Storage,
+    asset: &str,
+    feeder: &CanonicalAddr,
+    fed_price: &FedPrice,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_FED_PRICE, asset.as_bytes()])
+        .save(feeder.as_slice(), fed_price)
+}
+
+/// Last prices of all the feeders of an asset
+pub fn read_fed_prices(storage: &dyn Storage, asset: &str) -> StdResult<Vec<FedPrice>> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_FED_PRICE, asset.as_bytes()])
+        .range(None, None, Order::Ascending)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
@@ -0,0 +1,617 @@
+use crate::contract::{execute, instantiate, query, MAX_FEEDERS};
+use crate::error::ContractError;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{from_binary, Env, OwnedDeps, StdError};
+use moneymarket::oracle::{
+    ConfigResponse, ExecuteMsg, FeederResponse, InstantiateMsg, PriceResponse, PricesResponse,
+    PricesResponseElem, QueryMsg,
+};
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_asset: "uusd".to_string(),
+        price_window: 60,
+        max_deviation: Decimal256::percent(5),
+        min_feeders: 1,
+    }
+}
+
+fn mock_env_after_seconds(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn register_feeders(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, feeders: &[&str]) {
+    for feeder in feeders {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner0000", &[]),
+            ExecuteMsg::RegisterFeeder {
+                asset: "mAAPL".to_string(),
+                feeder: feeder.to_string(),
+            },
+        )
+        .unwrap();
+    }
+}
+
+fn feed_price(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    env: Env,
+    feeder: &str,
+    price: Decimal256,
+) {
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(feeder, &[]),
+        ExecuteMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), price)],
+        },
+    )
+    .unwrap();
+}
+
+fn query_price(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, env: Env) -> PriceResponse {
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Price {
+            base: "mAAPL".to_string(),
+            quote: "uusd".to_string(),
+        },
+    )
+    .unwrap();
+    from_binary(&res).unwrap()
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg();
+    msg.price_window = 0;
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::InvalidPriceWindow {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    for max_deviation in [Decimal256::zero(), Decimal256::percent(101)] {
+        let mut msg = instantiate_msg();
+        msg.max_deviation = max_deviation;
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidMaxDeviation { max_deviation })
+        );
+    }
+
+    for min_feeders in [0, MAX_FEEDERS as u64 + 1] {
+        let mut msg = instantiate_msg();
+        msg.min_feeders = min_feeders;
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidMinFeeders {
+                min_feeders,
+                max: MAX_FEEDERS,
+            })
+        );
+    }
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            base_asset: "uusd".to_string(),
+            price_window: 60,
+            max_deviation: Decimal256::percent(5),
+            min_feeders: 1,
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            price_window: Some(30),
+            max_deviation: None,
+            min_feeders: None,
+        },
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            price_window: None,
+            max_deviation: Some(Decimal256::percent(101)),
+            min_feeders: None,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::InvalidMaxDeviation {
+            max_deviation: Decimal256::percent(101)
+        })
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            price_window: None,
+            max_deviation: None,
+            min_feeders: Some(0),
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::InvalidMinFeeders {
+            min_feeders: 0,
+            max: MAX_FEEDERS,
+        })
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            price_window: Some(30),
+            max_deviation: Some(Decimal256::percent(10)),
+            min_feeders: Some(2),
+        },
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.price_window, 30);
+    assert_eq!(config_res.max_deviation, Decimal256::percent(10));
+    assert_eq!(config_res.min_feeders, 2);
+}
+
+#[test]
+fn register_feeders_of_an_asset() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::RegisterFeeder {
+        asset: "mAAPL".to_string(),
+        feeder: "feeder0000".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    register_feeders(&mut deps, &["feeder0000", "feeder0001"]);
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg);
+    match res {
+        Err(ContractError::FeederAlreadyRegistered { asset, feeder }) => {
+            assert_eq!(asset, "mAAPL");
+            assert_eq!(feeder, "feeder0000");
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Feeder {
+            asset: "mAAPL".to_string(),
+        },
+    )
+    .unwrap();
+    let feeder_res: FeederResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        feeder_res,
+        FeederResponse {
+            asset: "mAAPL".to_string(),
+            feeders: vec!["feeder0000".to_string(), "feeder0001".to_string()],
+        }
+    );
+
+    // only the feeders of an asset can feed it
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("feeder0002", &[]),
+        ExecuteMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), Decimal256::one())],
+        },
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("feeder0000", &[]),
+        ExecuteMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), Decimal256::zero())],
+        },
+    );
+    match res {
+        Err(ContractError::InvalidPrice { asset }) => assert_eq!(asset, "mAAPL"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a deregistered feeder loses its price and its permission
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0001",
+        Decimal256::percent(200),
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::DeregisterFeeder {
+            asset: "mAAPL".to_string(),
+            feeder: "feeder0001".to_string(),
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("feeder0001", &[]),
+        ExecuteMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), Decimal256::one())],
+        },
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Price {
+            base: "mAAPL".to_string(),
+            quote: "uusd".to_string(),
+        },
+    );
+    match res {
+        Err(StdError::NotFound { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::DeregisterFeeder {
+            asset: "mAAPL".to_string(),
+            feeder: "feeder0001".to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::FeederNotFound { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the number of feeders is bounded
+    let feeders: Vec<String> = (1..MAX_FEEDERS)
+        .map(|i| format!("feeder{:04}", i))
+        .collect();
+    register_feeders(
+        &mut deps,
+        &feeders.iter().map(|f| f.as_str()).collect::<Vec<&str>>(),
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::RegisterFeeder {
+            asset: "mAAPL".to_string(),
+            feeder: "feeder9999".to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::TooManyFeeders { asset, max }) => {
+            assert_eq!(asset, "mAAPL");
+            assert_eq!(max, MAX_FEEDERS);
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn median_of_fresh_prices() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+    register_feeders(&mut deps, &["feeder0000", "feeder0001", "feeder0002"]);
+
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0000",
+        Decimal256::percent(100),
+    );
+    feed_price(
+        &mut deps,
+        mock_env_after_seconds(10),
+        "feeder0001",
+        Decimal256::percent(102),
+    );
+    feed_price(
+        &mut deps,
+        mock_env_after_seconds(20),
+        "feeder0002",
+        Decimal256::percent(104),
+    );
+
+    // the aggregate is as old as its oldest price
+    let env = mock_env_after_seconds(30);
+    assert_eq!(
+        query_price(&deps, env.clone()),
+        PriceResponse {
+            rate: Decimal256::percent(102),
+            last_updated_base: mock_env().block.time.seconds(),
+            last_updated_quote: u64::MAX,
+        }
+    );
+
+    // the inverse rate
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Price {
+            base: "uusd".to_string(),
+            quote: "mAAPL".to_string(),
+        },
+    )
+    .unwrap();
+    let price_res: PriceResponse = from_binary(&res).unwrap();
+    assert_eq!(price_res.rate, Decimal256::one() / Decimal256::percent(102));
+
+    // the first price leaves the window; median of 1.02 and 1.04
+    let env = mock_env_after_seconds(65);
+    assert_eq!(
+        query_price(&deps, env.clone()),
+        PriceResponse {
+            rate: Decimal256::percent(103),
+            last_updated_base: mock_env_after_seconds(10).block.time.seconds(),
+            last_updated_quote: u64::MAX,
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Prices {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let prices_res: PricesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        prices_res,
+        PricesResponse {
+            prices: vec![PricesResponseElem {
+                asset: "mAAPL".to_string(),
+                price: Decimal256::percent(103),
+                last_updated_time: mock_env_after_seconds(10).block.time.seconds(),
+            }],
+        }
+    );
+
+    // no price is left in the window
+    let env = mock_env_after_seconds(100);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Price {
+            base: "mAAPL".to_string(),
+            quote: "uusd".to_string(),
+        },
+    );
+    match res {
+        Err(StdError::NotFound { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Prices {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let prices_res: PricesResponse = from_binary(&res).unwrap();
+    assert_eq!(prices_res.prices, vec![]);
+}
+
+#[test]
+fn outliers_are_rejected() {
+    let mut deps = mock_dependencies(&[]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+    register_feeders(
+        &mut deps,
+        &["feeder0000", "feeder0001", "feeder0002", "feeder0003"],
+    );
+
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0000",
+        Decimal256::percent(100),
+    );
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0001",
+        Decimal256::percent(101),
+    );
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0002",
+        Decimal256::percent(103),
+    );
+
+    // a single bad feed is more than 5% away from the median (1.02)
+    // and does not move the aggregate
+    feed_price(
+        &mut deps,
+        mock_env_after_seconds(1),
+        "feeder0003",
+        Decimal256::percent(1000),
+    );
+    assert_eq!(
+        query_price(&deps, mock_env_after_seconds(1)).rate,
+        Decimal256::percent(101)
+    );
+
+    // within the bound, the price is used
+    feed_price(
+        &mut deps,
+        mock_env_after_seconds(1),
+        "feeder0003",
+        Decimal256::percent(105),
+    );
+    assert_eq!(
+        query_price(&deps, mock_env_after_seconds(1)).rate,
+        Decimal256::percent(102)
+    );
+}
+
+#[test]
+fn quorum_of_feeders() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg();
+    msg.min_feeders = 3;
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    register_feeders(
+        &mut deps,
+        &["feeder0000", "feeder0001", "feeder0002", "feeder0003"],
+    );
+
+    let query_aggregate = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Price {
+                base: "mAAPL".to_string(),
+                quote: "uusd".to_string(),
+            },
+        )
+    };
+
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0000",
+        Decimal256::percent(100),
+    );
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0001",
+        Decimal256::percent(101),
+    );
+    match query_aggregate(&deps) {
+        Err(StdError::NotFound { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // an outlier does not count toward the quorum
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0002",
+        Decimal256::percent(1000),
+    );
+    match query_aggregate(&deps) {
+        Err(StdError::NotFound { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Prices {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let prices_res: PricesResponse = from_binary(&res).unwrap();
+    assert_eq!(prices_res.prices, vec![]);
+
+    feed_price(
+        &mut deps,
+        mock_env(),
+        "feeder0003",
+        Decimal256::percent(102),
+    );
+    assert_eq!(
+        query_price(&deps, mock_env()).rate,
+        Decimal256::percent(101)
+    );
+}
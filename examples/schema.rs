@@ -0,0 +1,37 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+
+use moneymarket::market::{
+    BorrowerInfoResponse, BorrowerInfosResponse, ConfigResponse, Cw20HookMsg, EpochStateResponse,
+    ExchangeRateHistoryResponse, ExecuteMsg, InstantiateMsg, MarketResponse, MarketsResponse,
+    MigrateMsg, QueryMsg, SimulationResponse, StateResponse, WithdrawalRequestResponse,
+    WithdrawalRequestsResponse,
+};
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(StateResponse), &out_dir);
+    export_schema(&schema_for!(EpochStateResponse), &out_dir);
+    export_schema(&schema_for!(BorrowerInfoResponse), &out_dir);
+    export_schema(&schema_for!(BorrowerInfosResponse), &out_dir);
+    export_schema(&schema_for!(WithdrawalRequestResponse), &out_dir);
+    export_schema(&schema_for!(WithdrawalRequestsResponse), &out_dir);
+    export_schema(&schema_for!(MarketResponse), &out_dir);
+    export_schema(&schema_for!(MarketsResponse), &out_dir);
+    export_schema(&schema_for!(MarketResponse), &out_dir);
+    export_schema(&schema_for!(MarketsResponse), &out_dir);
+    export_schema(&schema_for!(SimulationResponse), &out_dir);
+    export_schema(&schema_for!(ExchangeRateHistoryResponse), &out_dir);
+}
@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, QueryRequest, StdResult, WasmMsg, WasmQuery,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+
+use crate::querier::{compute_tax, query_balance};
+
+/// Prefix of the stable denoms backed by a cw20 token
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+/// Stable asset of a market; markets keep it as a denom string,
+/// where cw20 tokens are written `cw20:{contract address}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { contract_addr: String },
+}
+
+impl AssetInfo {
+    pub fn from_denom(denom: &str) -> Self {
+        match denom.strip_prefix(CW20_DENOM_PREFIX) {
+            Some(contract_addr) => AssetInfo::Cw20 {
+                contract_addr: contract_addr.to_string(),
+            },
+            None => AssetInfo::Native {
+                denom: denom.to_string(),
+            },
+        }
+    }
+
+    pub fn is_native(&self) -> bool {
+        matches!(self, AssetInfo::Native { .. })
+    }
+
+    pub fn query_balance(&self, deps: Deps, account_addr: Addr) -> StdResult<Uint256> {
+        match self {
+            AssetInfo::Native { denom } => query_balance(deps, account_addr, denom.to_string()),
+            AssetInfo::Cw20 { contract_addr } => {
+                let res: BalanceResponse =
+                    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                        contract_addr: contract_addr.to_string(),
+                        msg: to_binary(&Cw20QueryMsg::Balance {
+                            address: account_addr.to_string(),
+                        })?,
+                    }))?;
+
+                Ok(res.balance.into())
+            }
+        }
+    }
+
+    /// Tax charged on a transfer of `amount`; cw20 transfers are not taxed
+    pub fn compute_tax(&self, deps: Deps, amount: Uint256) -> StdResult<Uint256> {
+        match self {
+            AssetInfo::Native { denom } => compute_tax(
+                deps,
+                &Coin {
+                    denom: denom.to_string(),
+                    amount: amount.into(),
+                },
+            ),
+            AssetInfo::Cw20 { .. } => Ok(Uint256::zero()),
+        }
+    }
+
+    /// Amount received from a transfer of `amount`
+    pub fn deduct_tax(&self, deps: Deps, amount: Uint256) -> StdResult<Uint256> {
+        Ok(amount - self.compute_tax(deps, amount)?)
+    }
+
+    /// Send `amount` from the contract; the tax has to be deducted beforehand
+    pub fn transfer_msg(&self, recipient: &Addr, amount: Uint256) -> StdResult<CosmosMsg> {
+        Ok(match self {
+            AssetInfo::Native { denom } => CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin {
+                    denom: denom.to_string(),
+                    amount: amount.into(),
+                }],
+            }),
+            AssetInfo::Cw20 { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: amount.into(),
+                })?,
+            }),
+        })
+    }
+}
+
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetInfo::Native { denom } => write!(f, "{}", denom),
+            AssetInfo::Cw20 { contract_addr } => {
+                write!(f, "{}{}", CW20_DENOM_PREFIX, contract_addr)
+            }
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use cosmwasm_std::{Addr, Api, StdResult};
+use cosmwasm_std::{
+    attr, Addr, Api, Coin, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use cosmwasm_storage::{singleton, singleton_read};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Option<Addr>> {
     let addr = if let Some(addr) = addr {
@@ -9,3 +15,172 @@ pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<
 
     Ok(addr)
 }
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FundsError {
+    #[error("No funds sent")]
+    NoFunds {},
+
+    #[error("This message does not accept funds")]
+    UnexpectedFunds {},
+
+    #[error("Only one denom can be sent; got {count}")]
+    MultipleDenoms { count: usize },
+
+    #[error("Denom {denom} is not accepted")]
+    DenomNotAllowed { denom: String },
+}
+
+/// No-funds policy; for messages which do not move native coins
+pub fn assert_no_funds(funds: &[Coin]) -> Result<(), FundsError> {
+    if !funds.is_empty() {
+        return Err(FundsError::UnexpectedFunds {});
+    }
+
+    Ok(())
+}
+
+/// Exactly-one-denom policy; returns the sent coin
+pub fn assert_one_denom(funds: &[Coin]) -> Result<&Coin, FundsError> {
+    match funds {
+        [] => Err(FundsError::NoFunds {}),
+        [coin] => Ok(coin),
+        _ => Err(FundsError::MultipleDenoms { count: funds.len() }),
+    }
+}
+
+/// Allowed-denoms policy; sending nothing is allowed
+pub fn assert_allowed_denoms(funds: &[Coin], allowed: &[&str]) -> Result<(), FundsError> {
+    match funds.iter().find(|c| !allowed.contains(&c.denom.as_str())) {
+        Some(coin) => Err(FundsError::DenomNotAllowed {
+            denom: coin.denom.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OwnershipError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("New owner cannot be the same as the current owner")]
+    SameOwner {},
+
+    #[error("Ownership proposal expiry must not exceed {max} seconds; got {expires_in}")]
+    ExpiryTooLong { expires_in: u64, max: u64 },
+
+    #[error("Ownership proposal not found")]
+    ProposalNotFound {},
+
+    #[error("Ownership proposal expired at {expires_at}")]
+    ProposalExpired { expires_at: u64 },
+}
+
+pub static KEY_OWNERSHIP_PROPOSAL: &[u8] = b"ownership_proposal";
+
+/// Maximum lifetime of an ownership proposal in seconds (14 days)
+pub const MAX_PROPOSAL_TTL: u64 = 1209600;
+
+/// Pending ownership transfer; the proposed owner has to claim it
+/// before `expires_at` (block time in seconds)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub owner: Addr,
+    pub expires_at: u64,
+}
+
+pub fn read_ownership_proposal(storage: &dyn Storage) -> StdResult<Option<OwnershipProposal>> {
+    singleton_read(storage, KEY_OWNERSHIP_PROPOSAL).may_load()
+}
+
+/// Store a new ownership proposal, replacing the pending one if any.
+/// `owner` is the current owner of the calling contract
+pub fn propose_new_owner(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    owner: &Addr,
+    new_owner: String,
+    expires_in: u64,
+) -> Result<Response, OwnershipError> {
+    if info.sender != *owner {
+        return Err(OwnershipError::Unauthorized {});
+    }
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    if new_owner == *owner {
+        return Err(OwnershipError::SameOwner {});
+    }
+
+    if expires_in > MAX_PROPOSAL_TTL {
+        return Err(OwnershipError::ExpiryTooLong {
+            expires_in,
+            max: MAX_PROPOSAL_TTL,
+        });
+    }
+
+    let expires_at = env.block.time.seconds() + expires_in;
+    singleton(deps.storage, KEY_OWNERSHIP_PROPOSAL).save(&OwnershipProposal {
+        owner: new_owner.clone(),
+        expires_at,
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("new_owner", new_owner),
+        attr("expires_at", expires_at.to_string()),
+    ]))
+}
+
+/// Drop the pending ownership proposal; only the current owner can do it
+pub fn drop_ownership_proposal(
+    deps: DepsMut,
+    info: &MessageInfo,
+    owner: &Addr,
+) -> Result<Response, OwnershipError> {
+    if info.sender != *owner {
+        return Err(OwnershipError::Unauthorized {});
+    }
+
+    if read_ownership_proposal(deps.storage)?.is_none() {
+        return Err(OwnershipError::ProposalNotFound {});
+    }
+
+    singleton::<OwnershipProposal>(deps.storage, KEY_OWNERSHIP_PROPOSAL).remove();
+
+    Ok(Response::new().add_attributes(vec![attr("action", "reject_ownership_proposal")]))
+}
+
+/// Accept the pending ownership proposal; `update_owner` stores the
+/// new owner into the calling contract's config
+pub fn claim_ownership(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    update_owner: fn(DepsMut, Addr) -> StdResult<()>,
+) -> Result<Response, OwnershipError> {
+    let proposal =
+        read_ownership_proposal(deps.storage)?.ok_or(OwnershipError::ProposalNotFound {})?;
+
+    if info.sender != proposal.owner {
+        return Err(OwnershipError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() > proposal.expires_at {
+        return Err(OwnershipError::ProposalExpired {
+            expires_at: proposal.expires_at,
+        });
+    }
+
+    singleton::<OwnershipProposal>(deps.storage, KEY_OWNERSHIP_PROPOSAL).remove();
+    update_owner(deps, proposal.owner.clone())?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "claim_ownership"),
+        attr("new_owner", proposal.owner),
+    ]))
+}
@@ -37,9 +37,16 @@ pub enum ExecuteMsg {
 
     /// Update config
     UpdateConfig {
-        owner: Option<String>,
         liquidation_contract: Option<String>,
     },
+
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
     /// Make specified amount of tokens unspendable
     LockCollateral { borrower: String, amount: Uint256 },
     /// Make specified amount of collateral tokens spendable
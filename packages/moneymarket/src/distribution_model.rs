@@ -16,12 +16,19 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         emission_cap: Option<Decimal256>,
         emission_floor: Option<Decimal256>,
         increment_multiplier: Option<Decimal256>,
         decrement_multiplier: Option<Decimal256>,
     },
+
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -8,16 +8,31 @@ pub struct InstantiateMsg {
     pub owner: String,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    /// Utilization above which `jump_multiplier` applies;
+    /// one (the default) keeps the model linear
+    pub optimal_utilization: Option<Decimal256>,
+    /// Multiplier of the utilization above `optimal_utilization`;
+    /// `interest_multiplier` by default
+    pub jump_multiplier: Option<Decimal256>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         base_rate: Option<Decimal256>,
         interest_multiplier: Option<Decimal256>,
+        optimal_utilization: Option<Decimal256>,
+        jump_multiplier: Option<Decimal256>,
     },
+
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -37,6 +52,8 @@ pub struct ConfigResponse {
     pub owner: String,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    pub optimal_utilization: Decimal256,
+    pub jump_multiplier: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -1,3 +1,4 @@
+pub mod asset;
 pub mod common;
 pub mod custody;
 pub mod distribution_model;
@@ -7,6 +8,7 @@ pub mod liquidation_queue;
 pub mod market;
 pub mod oracle;
 pub mod overseer;
+pub mod price_guard;
 pub mod querier;
 pub mod tokens;
 
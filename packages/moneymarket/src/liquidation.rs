@@ -33,7 +33,6 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     UpdateConfig {
-        owner: Option<String>,
         oracle_contract: Option<String>,
         stable_denom: Option<String>,
         safe_ratio: Option<Decimal256>,
@@ -42,6 +41,17 @@ pub enum ExecuteMsg {
         liquidation_threshold: Option<Uint256>,
         price_timeframe: Option<u64>,
     },
+
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
     SubmitBid {
         collateral_token: String,
         premium_rate: Decimal256,
@@ -5,6 +5,7 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::Uint128;
 use cw20::Cw20ReceiveMsg;
 
+use crate::price_guard::PriceGuardConfig;
 use crate::tokens::TokensHuman;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -30,6 +31,8 @@ pub struct InstantiateMsg {
     /// Time period that needs to pass for a bid to be activated (seconds)
     pub waiting_period: u64,
     pub overseer: String,
+    /// Opt into the oracle price circuit breaker
+    pub price_guard: Option<PriceGuardConfig>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -37,7 +40,6 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     UpdateConfig {
-        owner: Option<String>,
         oracle_contract: Option<String>,
         safe_ratio: Option<Decimal256>,
         bid_fee: Option<Decimal256>,
@@ -46,7 +48,27 @@ pub enum ExecuteMsg {
         price_timeframe: Option<u64>,
         waiting_period: Option<u64>,
         overseer: Option<String>,
+        price_guard: Option<PriceGuardConfig>,
+        /// Turn the price guard off and drop its references;
+        /// takes precedence over `price_guard`
+        disable_price_guard: Option<bool>,
     },
+    /// Re-anchor the price guard of a pair on the current oracle rate
+    ResetPriceGuard {
+        base: String,
+        quote: String,
+    },
+
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
     /// Owner operation to whitelist a new collateral
     WhitelistCollateral {
         collateral_token: String,
@@ -123,6 +145,10 @@ pub enum QueryMsg {
         start_after: Option<u8>,
         limit: Option<u8>,
     },
+    PriceGuard {
+        base: String,
+        quote: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -137,6 +163,7 @@ pub struct ConfigResponse {
     pub price_timeframe: u64,
     pub waiting_period: u64,
     pub overseer: String,
+    pub price_guard: Option<PriceGuardConfig>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -4,12 +4,16 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cw20::Cw20ReceiveMsg;
 
+/// Seconds in a 365 days year, to annualize yields
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     /// Owner address for config update
     pub owner_addr: String,
-    /// stable coin denom used to borrow & repay
+    /// stable coin denom used to borrow & repay; a cw20 token
+    /// (`cw20:{contract address}`) opens with the `InitialDeposit` hook
     pub stable_denom: String,
     /// Anchor token code ID used to instantiate
     pub aterra_code_id: u64,
@@ -17,6 +21,12 @@ pub struct InstantiateMsg {
     pub anc_emission_rate: Decimal256,
     /// Maximum allowed borrow rate over deposited stable balance
     pub max_borrow_factor: Decimal256,
+    /// aterra token name; required unless `stable_denom` is a native
+    /// micro denom, which derives it (`uusd` is `Anchor Terra USD`)
+    pub aterra_name: Option<String>,
+    /// aterra token symbol; required unless `stable_denom` is a native
+    /// micro denom, which derives it (`uusd` is `aUST`)
+    pub aterra_symbol: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -44,12 +54,45 @@ pub enum ExecuteMsg {
 
     /// Update config values
     UpdateConfig {
-        owner_addr: Option<String>,
         max_borrow_factor: Option<Decimal256>,
         interest_model: Option<String>,
         distribution_model: Option<String>,
     },
 
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
+
+    /// Open a market for another stable denom with its own aterra
+    /// token; the initial deposit has to be sent along.
+    ///
+    /// An added market has its own state, liabilities, reserves and
+    /// withdrawal queue, and shares the interest model of the primary
+    /// market. The messages and queries taking an optional `denom` select
+    /// it; the primary market is the default. ANC rewards and the
+    /// exchange rate history only cover the primary market
+    AddMarket {
+        stable_denom: String,
+        aterra_code_id: u64,
+        /// Same as the instantiate `aterra_name`
+        aterra_name: Option<String>,
+        /// Same as the instantiate `aterra_symbol`
+        aterra_symbol: Option<String>,
+    },
+    /// Close the market of a stable denom once all its deposits are
+    /// redeemed and its loans and withdrawal requests are settled;
+    /// the initial deposit is returned to the owner
+    RemoveMarket {
+        stable_denom: String,
+    },
+
     ////////////////////
     /// Overseer operations
     ////////////////////
@@ -57,10 +100,12 @@ pub enum ExecuteMsg {
     RepayStableFromLiquidation {
         borrower: String,
         prev_balance: Uint256,
+        /// Market of the repaid loan; the primary market by default
+        denom: Option<String>,
     },
 
     /// Execute epoch operations
-    /// 1. send reserve to collector contract
+    /// 1. send reserve to collector contract, for the added markets as well
     /// 2. update anc_emission_rate state
     ExecuteEpochOperations {
         deposit_rate: Decimal256,
@@ -72,38 +117,90 @@ pub enum ExecuteMsg {
     ////////////////////
     /// User operations
     ////////////////////
-    /// Deposit stable asset to get interest
-    DepositStable {},
+    /// Deposit stable asset to get interest; the market is picked by
+    /// the denom of the sent coins, and its aterra is minted to the
+    /// recipient, the sender by default
+    DepositStable {
+        recipient: Option<String>,
+        /// Fails when less aterra than this would be minted
+        min_mint_amount: Option<Uint256>,
+    },
 
-    /// Borrow stable asset with collaterals in overseer contract
+    /// Borrow stable asset with collaterals in overseer contract; the
+    /// borrow limit covers the loans of all markets, valued in the
+    /// primary denom at the oracle price
     BorrowStable {
         borrow_amount: Uint256,
         to: Option<String>,
+        /// Market to borrow from; the primary market by default
+        denom: Option<String>,
     },
 
-    /// Repay stable asset to decrease liability
+    /// Repay stable asset to decrease liability; the market is
+    /// picked by the denom of the sent coins
     RepayStable {},
 
     /// Claim distributed ANC rewards
     ClaimRewards {
         to: Option<String>,
     },
+
+    /// Cancel a queued withdrawal and get the escrowed aterra back
+    CancelWithdrawal {
+        id: u64,
+        /// Market of the request; the primary market by default
+        denom: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     /// Return stable coins to a user
-    /// according to exchange rate
-    RedeemStable {},
+    /// according to exchange rate;
+    /// stables are sent to the recipient, the cw20 sender by default
+    RedeemStable {
+        recipient: Option<String>,
+        /// Fails when less stable than this would be received, after tax
+        min_receive: Option<Uint256>,
+        /// When the market liquidity is short, redeem what is available
+        /// and return the rest of the aterra instead of failing
+        allow_partial: Option<bool>,
+    },
+    /// Return exactly `amount` stable coins, after tax, to a user;
+    /// the aterra which is not needed is sent back
+    RedeemExactStable {
+        amount: Uint256,
+        recipient: Option<String>,
+    },
+    /// Escrow aterra in the withdrawal queue; it is redeemed
+    /// first-in-first-out as the market liquidity returns
+    QueueRedeem { recipient: Option<String> },
+    /// Deposit the received cw20 stable tokens into their market;
+    /// aterra is minted to the recipient, the cw20 sender by default
+    DepositStable {
+        recipient: Option<String>,
+        /// Fails when less aterra than this would be minted
+        min_mint_amount: Option<Uint256>,
+    },
+    /// Repay the loan of the cw20 sender with the received cw20
+    /// stable tokens, in the market of the token
+    RepayStable {},
+    /// Owner operation to fund the initial deposit of a cw20 primary
+    /// market, which has to be exactly the initial deposit amount;
+    /// the market is closed until then
+    InitialDeposit {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    /// The queries taking an optional `denom` serve the market of
+    /// that denom; the primary market by default
     State {
         block_height: Option<u64>,
+        denom: Option<String>,
     },
     EpochState {
         block_height: Option<u64>,
@@ -112,10 +209,45 @@ pub enum QueryMsg {
     BorrowerInfo {
         borrower: String,
         block_height: Option<u64>,
+        denom: Option<String>,
     },
     BorrowerInfos {
         start_after: Option<String>,
         limit: Option<u32>,
+        denom: Option<String>,
+    },
+    WithdrawalRequest {
+        id: u64,
+        denom: Option<String>,
+    },
+    WithdrawalRequests {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        denom: Option<String>,
+    },
+    /// A market by stable denom; the primary market included
+    Market {
+        denom: String,
+    },
+    Markets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    SimulateDeposit {
+        amount: Uint256,
+        block_height: Option<u64>,
+        denom: Option<String>,
+    },
+    SimulateRedeem {
+        aterra_amount: Uint256,
+        block_height: Option<u64>,
+        denom: Option<String>,
+    },
+    /// Exchange rate snapshots taken at the epoch operations,
+    /// from the oldest to the most recent
+    ExchangeRateHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
     },
 }
 
@@ -154,6 +286,69 @@ pub struct EpochStateResponse {
     pub aterra_supply: Uint256,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketResponse {
+    pub stable_denom: String,
+    pub aterra_contract: String,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketsResponse {
+    pub markets: Vec<MarketResponse>,
+}
+
+/// Outcome of a deposit or redeem at the given block; `output_amount` is
+/// the minted aterra for deposits and the received stable for redeems
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulationResponse {
+    pub exchange_rate: Decimal256,
+    pub output_amount: Uint256,
+    pub tax_amount: Uint256,
+    pub sufficient_liquidity: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateSnapshotResponse {
+    pub id: u64,
+    pub height: u64,
+    /// Block time in seconds
+    pub time: u64,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub total_liabilities: Decimal256,
+}
+
+impl ExchangeRateSnapshotResponse {
+    /// Simple (non compounded) yearly yield of aterra between this snapshot
+    /// and a later one; None when `later` is not after this snapshot
+    pub fn annualized_yield(&self, later: &ExchangeRateSnapshotResponse) -> Option<Decimal256> {
+        if later.time <= self.time || self.exchange_rate.is_zero() {
+            return None;
+        }
+
+        // the exchange rate never decreases, unless the market lost funds
+        if later.exchange_rate <= self.exchange_rate {
+            return Some(Decimal256::zero());
+        }
+
+        let growth = later.exchange_rate / self.exchange_rate - Decimal256::one();
+        Some(growth * Decimal256::from_ratio(SECONDS_PER_YEAR, later.time - self.time))
+    }
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateHistoryResponse {
+    pub snapshots: Vec<ExchangeRateSnapshotResponse>,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfoResponse {
@@ -169,3 +364,27 @@ pub struct BorrowerInfoResponse {
 pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalRequestResponse {
+    pub id: u64,
+    pub owner: String,
+    pub recipient: String,
+    pub aterra_amount: Uint256,
+    /// Number of requests to be settled before this one
+    pub position: u64,
+    /// Aterra amount to be settled before this one
+    pub aterra_ahead: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalRequestsResponse {
+    pub total_aterra: Uint256,
+    pub requests: Vec<WithdrawalRequestResponse>,
+}
+
+/// We currently take no arguments for migrations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
@@ -6,7 +6,7 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+use crate::oracle::{price_not_found, PriceResponse, QueryMsg as OracleQueryMsg};
 
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
@@ -131,7 +131,11 @@ impl WasmMockQuerier {
                 msg,
             }) => match from_binary(msg).unwrap() {
                 OracleQueryMsg::Price { base, quote } => {
-                    match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
+                    match self
+                        .oracle_price_querier
+                        .oracle_price
+                        .get(&(base.clone(), quote))
+                    {
                         Some(v) => {
                             SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
                                 rate: v.0,
@@ -139,10 +143,9 @@ impl WasmMockQuerier {
                                 last_updated_quote: v.2,
                             })))
                         }
-                        None => SystemResult::Err(SystemError::InvalidRequest {
-                            error: "No oracle price exists".to_string(),
-                            request: msg.as_slice().into(),
-                        }),
+                        None => SystemResult::Ok(ContractResult::Err(
+                            price_not_found(&base).to_string(),
+                        )),
                     }
                 }
                 _ => panic!("DO NOT ENTER HERE"),
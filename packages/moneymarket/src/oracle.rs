@@ -2,23 +2,40 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::StdError;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
     pub base_asset: String,
+    /// Seconds a fed price is used in the aggregate
+    pub price_window: u64,
+    /// Largest relative distance from the median of the fed prices;
+    /// prices further away are left out of the aggregate
+    pub max_deviation: Decimal256,
+    /// Fewest fresh prices within `max_deviation` an aggregate needs
+    pub min_feeders: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
     UpdateConfig {
-        owner: Option<String>,
-    },
-    RegisterFeeder {
-        asset: String,
-        feeder: String,
+        price_window: Option<u64>,
+        max_deviation: Option<Decimal256>,
+        min_feeders: Option<u64>,
     },
+    /// Allow `feeder` to feed the price of `asset`, next to its other feeders
+    RegisterFeeder { asset: String, feeder: String },
+    /// Revoke a feeder of `asset` and drop its last fed price
+    DeregisterFeeder { asset: String, feeder: String },
     FeedPrice {
         prices: Vec<(String, Decimal256)>, // (asset, price)
     },
@@ -46,18 +63,23 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     pub owner: String,
     pub base_asset: String,
+    pub price_window: u64,
+    pub max_deviation: Decimal256,
+    pub min_feeders: u64,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FeederResponse {
     pub asset: String,
-    pub feeder: String,
+    pub feeders: Vec<String>,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceResponse {
+    /// Price of `base` in `quote`; the `last_updated` times are
+    /// the oldest fed price used in each aggregate
     pub rate: Decimal256,
     pub last_updated_base: u64,
     pub last_updated_quote: u64,
@@ -76,3 +98,9 @@ pub struct PricesResponseElem {
 pub struct PricesResponse {
     pub prices: Vec<PricesResponseElem>,
 }
+
+/// Error of the price query for an asset without a price; the consumers
+/// tell a missing price apart from any other failure of the oracle by it
+pub fn price_not_found(asset: &str) -> StdError {
+    StdError::not_found(format!("{} price", asset))
+}
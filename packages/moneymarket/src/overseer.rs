@@ -1,6 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::price_guard::PriceGuardConfig;
 use crate::tokens::TokensHuman;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 
@@ -35,6 +36,8 @@ pub struct InstantiateMsg {
     pub anc_purchase_factor: Decimal256,
     /// Valid oracle price timeframe
     pub price_timeframe: u64,
+    /// Opt into the oracle price circuit breaker
+    pub price_guard: Option<PriceGuardConfig>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -46,7 +49,6 @@ pub enum ExecuteMsg {
 
     /// Update Configs
     UpdateConfig {
-        owner_addr: Option<String>,
         oracle_contract: Option<String>,
         liquidation_contract: Option<String>,
         threshold_deposit_rate: Option<Decimal256>,
@@ -55,7 +57,21 @@ pub enum ExecuteMsg {
         anc_purchase_factor: Option<Decimal256>,
         epoch_period: Option<u64>,
         price_timeframe: Option<u64>,
+        price_guard: Option<PriceGuardConfig>,
+        /// Turn the price guard off and drop its references;
+        /// takes precedence over `price_guard`
+        disable_price_guard: Option<bool>,
     },
+    /// Re-anchor the price guard of a pair on the current oracle rate
+    ResetPriceGuard { base: String, quote: String },
+
+    /// Propose a new owner; the proposal has to be claimed by the
+    /// new owner within `expires_in` seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Accept the pending ownership proposal
+    ClaimOwnership {},
+    /// Drop the pending ownership proposal
+    RejectOwnershipProposal {},
 
     /// Create new custody contract for the given collateral token
     Whitelist {
@@ -120,6 +136,10 @@ pub enum QueryMsg {
         borrower: String,
         block_time: Option<u64>,
     },
+    PriceGuard {
+        base: String,
+        quote: String,
+    },
 }
 
 // We define a custom struct for each query response
@@ -137,6 +157,7 @@ pub struct ConfigResponse {
     pub stable_denom: String,
     pub epoch_period: u64,
     pub price_timeframe: u64,
+    pub price_guard: Option<PriceGuardConfig>,
 }
 
 // We define a custom struct for each query response
@@ -0,0 +1,258 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{Addr, Deps, DepsMut, Order, StdError, StdResult, Storage};
+use cosmwasm_storage::{Bucket, PrefixedStorage, ReadonlyBucket, ReadonlySingleton, Singleton};
+
+use crate::oracle::PriceResponse;
+use crate::querier::{query_price, PriceError, TimeConstraints};
+
+pub static KEY_PRICE_GUARD_CONFIG: &[u8] = b"price_guard_config";
+pub static PREFIX_PRICE_REFERENCE: &[u8] = b"price_reference";
+
+/// Circuit breaker settings; a rate may move by at most `max_change`
+/// (a ratio of the reference rate) per `window` blocks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceGuardConfig {
+    pub max_change: Decimal256,
+    pub window: u64,
+}
+
+/// Last accepted rate of a pair, anchoring the current window
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceReference {
+    pub rate: Decimal256,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceGuardResponse {
+    pub base: String,
+    pub quote: String,
+    /// None when the guard is disabled
+    pub config: Option<PriceGuardConfig>,
+    /// None until the first guarded price of the pair
+    pub reference: Option<PriceReference>,
+    pub rate: Decimal256,
+    /// Whether the current oracle rate would be refused
+    pub tripped: bool,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PriceGuardError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Price(#[from] PriceError),
+
+    #[error("Price guard window must be greater than 0")]
+    InvalidWindow {},
+
+    #[error("Price guard max change must be greater than 0 and at most 1: {max_change}")]
+    InvalidMaxChange { max_change: Decimal256 },
+
+    #[error("Price guard tripped: {base}/{quote} moved from {reference_rate} to {rate}, beyond the allowed change")]
+    Tripped {
+        base: String,
+        quote: String,
+        reference_rate: Decimal256,
+        rate: Decimal256,
+    },
+}
+
+/// Enable the guard, or change its settings; the stored references are kept.
+/// A zero `max_change` would freeze the rate, and above 1 a rate could
+/// fall to zero within a single window
+pub fn store_price_guard_config(
+    storage: &mut dyn Storage,
+    config: &PriceGuardConfig,
+) -> Result<(), PriceGuardError> {
+    if config.window == 0 {
+        return Err(PriceGuardError::InvalidWindow {});
+    }
+
+    if config.max_change.is_zero() || config.max_change > Decimal256::one() {
+        return Err(PriceGuardError::InvalidMaxChange {
+            max_change: config.max_change,
+        });
+    }
+
+    Singleton::new(storage, KEY_PRICE_GUARD_CONFIG).save(config)?;
+    Ok(())
+}
+
+/// Disable the guard; the references of all pairs are dropped as well,
+/// so enabling it again starts from the next prices
+pub fn remove_price_guard_config(storage: &mut dyn Storage) {
+    Singleton::<PriceGuardConfig>::new(storage, KEY_PRICE_GUARD_CONFIG).remove();
+
+    let mut references = PrefixedStorage::new(storage, PREFIX_PRICE_REFERENCE);
+    let keys: Vec<Vec<u8>> = references
+        .range(None, None, Order::Ascending)
+        .map(|(key, _)| key)
+        .collect();
+    for key in keys {
+        references.remove(&key);
+    }
+}
+
+/// None when the consumer has not opted into the guard
+pub fn read_price_guard_config(storage: &dyn Storage) -> StdResult<Option<PriceGuardConfig>> {
+    ReadonlySingleton::new(storage, KEY_PRICE_GUARD_CONFIG).may_load()
+}
+
+pub fn store_price_reference(
+    storage: &mut dyn Storage,
+    base: &str,
+    quote: &str,
+    reference: &PriceReference,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_PRICE_REFERENCE, base.as_bytes()])
+        .save(quote.as_bytes(), reference)
+}
+
+pub fn read_price_reference(
+    storage: &dyn Storage,
+    base: &str,
+    quote: &str,
+) -> StdResult<Option<PriceReference>> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_PRICE_REFERENCE, base.as_bytes()])
+        .may_load(quote.as_bytes())
+}
+
+/// Whether `rate` is within the band around the reference at `height`.
+/// The band widens by `max_change` for every full window elapsed since
+/// the reference was taken
+pub fn is_within_band(
+    config: &PriceGuardConfig,
+    reference: &PriceReference,
+    rate: Decimal256,
+    height: u64,
+) -> bool {
+    let windows = (height.saturating_sub(reference.height) / config.window).max(1);
+    let max_distance = reference.rate * config.max_change * Decimal256::from_uint256(windows);
+
+    let distance = if rate > reference.rate {
+        rate - reference.rate
+    } else {
+        reference.rate - rate
+    };
+
+    distance <= max_distance
+}
+
+/// Check a new rate of `base` in `quote` against the stored reference.
+/// The first rate of a pair becomes its reference; afterwards the reference
+/// only moves once a full window has passed, so several moves within the
+/// same window cannot add up beyond `max_change`. Does nothing while the
+/// guard is disabled
+pub fn guard_price(
+    storage: &mut dyn Storage,
+    height: u64,
+    base: &str,
+    quote: &str,
+    rate: Decimal256,
+) -> Result<(), PriceGuardError> {
+    let config = match read_price_guard_config(storage)? {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    if let Some(reference) = read_price_reference(storage, base, quote)? {
+        if !is_within_band(&config, &reference, rate, height) {
+            return Err(PriceGuardError::Tripped {
+                base: base.to_string(),
+                quote: quote.to_string(),
+                reference_rate: reference.rate,
+                rate,
+            });
+        }
+
+        if height < reference.height.saturating_add(config.window) {
+            return Ok(());
+        }
+    }
+
+    store_price_reference(storage, base, quote, &PriceReference { rate, height })?;
+    Ok(())
+}
+
+/// Oracle price checked by the guard; drop-in replacement of `query_price`
+/// for the consumers which opted into the guard
+pub fn query_guarded_price(
+    deps: DepsMut,
+    oracle_addr: Addr,
+    base: String,
+    quote: String,
+    time_contraints: Option<TimeConstraints>,
+    height: u64,
+) -> Result<PriceResponse, PriceGuardError> {
+    let price = query_price(
+        deps.as_ref(),
+        oracle_addr,
+        base.clone(),
+        quote.clone(),
+        time_contraints,
+    )?;
+
+    guard_price(deps.storage, height, &base, &quote, price.rate)?;
+    Ok(price)
+}
+
+/// Re-anchor the reference of a pair on the current oracle rate, to
+/// resume after a legitimate move tripped the guard. Permission checks
+/// are up to the caller
+pub fn reset_price_reference(
+    deps: DepsMut,
+    oracle_addr: Addr,
+    base: String,
+    quote: String,
+    height: u64,
+) -> StdResult<PriceReference> {
+    let price = query_price(
+        deps.as_ref(),
+        oracle_addr,
+        base.clone(),
+        quote.clone(),
+        None,
+    )?;
+    let reference = PriceReference {
+        rate: price.rate,
+        height,
+    };
+
+    store_price_reference(deps.storage, &base, &quote, &reference)?;
+    Ok(reference)
+}
+
+/// State of the guard for a pair, evaluated against the current oracle rate.
+/// A refused price reverts with its transaction, so the tripped state is
+/// derived here rather than stored
+pub fn query_price_guard(
+    deps: Deps,
+    oracle_addr: Addr,
+    base: String,
+    quote: String,
+    height: u64,
+) -> StdResult<PriceGuardResponse> {
+    let config = read_price_guard_config(deps.storage)?;
+    let reference = read_price_reference(deps.storage, &base, &quote)?;
+    let price = query_price(deps, oracle_addr, base.clone(), quote.clone(), None)?;
+
+    let tripped = match (&config, &reference) {
+        (Some(config), Some(reference)) => !is_within_band(config, reference, price.rate, height),
+        _ => false,
+    };
+
+    Ok(PriceGuardResponse {
+        base,
+        quote,
+        config,
+        reference,
+        rate: price.rate,
+        tripped,
+    })
+}
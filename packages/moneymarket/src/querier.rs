@@ -1,15 +1,17 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    to_binary, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Coin, Deps, QueryRequest,
-    StdError, StdResult, Uint128, WasmQuery,
+    from_binary, to_binary, to_vec, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Coin,
+    ContractResult, Deps, Empty, QueryRequest, StdError, StdResult, SystemResult, Uint128,
+    WasmQuery,
 };
-use cw20::{Cw20QueryMsg, TokenInfoResponse};
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use terra_cosmwasm::TerraQuerier;
 
-use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+use crate::oracle::{price_not_found, PriceResponse, QueryMsg as OracleQueryMsg};
 
 pub fn query_all_balances(deps: Deps, account_addr: Addr) -> StdResult<Vec<Coin>> {
     // load price form the oracle
@@ -44,6 +46,7 @@ pub fn query_token_balance(
                 address: account_addr.to_string(),
             })?,
         }))
+        .map(|res: Cw20BalanceResponse| res.balance)
         .unwrap_or_else(|_| Uint128::zero());
 
     Ok(balance.into())
@@ -97,27 +100,113 @@ pub struct TimeConstraints {
     pub valid_timeframe: u64,
 }
 
+#[derive(Error, Debug, PartialEq)]
+pub enum PriceError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Price of {asset} is too old: last updated at {last_updated}, expected {min_allowed} or later")]
+    Stale {
+        asset: String,
+        last_updated: u64,
+        min_allowed: u64,
+    },
+
+    #[error("No price of {base} in {quote}")]
+    Missing { base: String, quote: String },
+
+    #[error("Price of {asset} is updated in the future: last updated at {last_updated}, block time is {block_time}")]
+    FutureTimestamp {
+        asset: String,
+        last_updated: u64,
+        block_time: u64,
+    },
+}
+
+impl From<PriceError> for StdError {
+    fn from(err: PriceError) -> Self {
+        match err {
+            PriceError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
+
+/// Oracle rate of `base` in `quote`. With time constraints, both
+/// prices have to be updated within `valid_timeframe` seconds before
+/// `block_time`; `u64::MAX` marks a price which never gets outdated
 pub fn query_price(
     deps: Deps,
     oracle_addr: Addr,
     base: String,
     quote: String,
     time_contraints: Option<TimeConstraints>,
-) -> StdResult<PriceResponse> {
-    let oracle_price: PriceResponse =
-        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: oracle_addr.to_string(),
-            msg: to_binary(&OracleQueryMsg::Price { base, quote })?,
-        }))?;
+) -> Result<PriceResponse, PriceError> {
+    let request = to_vec(&QueryRequest::<Empty>::Wasm(WasmQuery::Smart {
+        contract_addr: oracle_addr.to_string(),
+        msg: to_binary(&OracleQueryMsg::Price {
+            base: base.clone(),
+            quote: quote.clone(),
+        })?,
+    }))?;
 
-    if let Some(time_contraints) = time_contraints {
-        let valid_update_time = time_contraints.block_time - time_contraints.valid_timeframe;
-        if oracle_price.last_updated_base < valid_update_time
-            || oracle_price.last_updated_quote < valid_update_time
+    // only the price-not-found answer of the oracle for one of the assets
+    // means the price is missing; any other failure is passed through
+    let oracle_price: PriceResponse = match deps.querier.raw_query(&request) {
+        SystemResult::Ok(ContractResult::Ok(value)) => from_binary(&value)?,
+        SystemResult::Ok(ContractResult::Err(error))
+            if is_price_not_found(&error, &base) || is_price_not_found(&error, &quote) =>
         {
-            return Err(StdError::generic_err("Price is too old"));
+            return Err(PriceError::Missing { base, quote });
+        }
+        SystemResult::Ok(ContractResult::Err(error)) => {
+            return Err(StdError::generic_err(format!("Querier contract error: {}", error)).into());
+        }
+        SystemResult::Err(error) => {
+            return Err(StdError::generic_err(format!("Querier system error: {}", error)).into());
         }
+    };
+
+    if let Some(time_contraints) = time_contraints {
+        assert_price_time(&time_contraints, base, oracle_price.last_updated_base)?;
+        assert_price_time(&time_contraints, quote, oracle_price.last_updated_quote)?;
     }
 
     Ok(oracle_price)
 }
+
+/// The error may carry the context added by the chain after the message
+fn is_price_not_found(error: &str, asset: &str) -> bool {
+    error.starts_with(&price_not_found(asset).to_string())
+}
+
+fn assert_price_time(
+    time_contraints: &TimeConstraints,
+    asset: String,
+    last_updated: u64,
+) -> Result<(), PriceError> {
+    if last_updated == u64::MAX {
+        return Ok(());
+    }
+
+    if last_updated > time_contraints.block_time {
+        return Err(PriceError::FutureTimestamp {
+            asset,
+            last_updated,
+            block_time: time_contraints.block_time,
+        });
+    }
+
+    let min_allowed = time_contraints
+        .block_time
+        .saturating_sub(time_contraints.valid_timeframe);
+    if last_updated < min_allowed {
+        return Err(PriceError::Stale {
+            asset,
+            last_updated,
+            min_allowed,
+        });
+    }
+
+    Ok(())
+}
@@ -1,10 +1,22 @@
+use crate::common::{assert_allowed_denoms, assert_no_funds, assert_one_denom, FundsError};
 use crate::mock_querier::mock_dependencies;
-use crate::oracle::PriceResponse;
-use crate::querier::{compute_tax, deduct_tax, query_price, query_tax_rate, TimeConstraints};
+use crate::oracle::{price_not_found, PriceResponse};
+use crate::price_guard::{
+    guard_price, query_guarded_price, query_price_guard, read_price_guard_config,
+    read_price_reference, remove_price_guard_config, reset_price_reference,
+    store_price_guard_config, PriceGuardConfig, PriceGuardError, PriceReference,
+};
+use crate::querier::{
+    compute_tax, deduct_tax, query_price, query_tax_rate, PriceError, TimeConstraints,
+};
 use crate::tokens::{Tokens, TokensHuman, TokensMath, TokensToRaw};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Coin, Decimal, StdError, Uint128};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    Addr, Api, CanonicalAddr, Coin, ContractResult, Decimal, OwnedDeps, Querier, QuerierResult,
+    StdError, SystemError, SystemResult, Uint128,
+};
 
 #[test]
 fn tax_rate_querier() {
@@ -114,8 +126,152 @@ fn oracle_price_querier() {
         }),
     );
 
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Price is too old"),
+    assert_eq!(
+        res,
+        Err(PriceError::Stale {
+            asset: "terra123123".to_string(),
+            last_updated: 123,
+            min_allowed: 440,
+        })
+    );
+}
+
+#[test]
+fn oracle_price_time_constraints() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("terra123123".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), 100, 200),
+        ),
+        (
+            &("uusd".to_string(), "terra123123".to_string()),
+            &(Decimal256::one(), u64::MAX, 100),
+        ),
+    ]);
+
+    let query = |base: &str, quote: &str, block_time: u64, valid_timeframe: u64| {
+        query_price(
+            deps.as_ref(),
+            Addr::unchecked("oracle"),
+            base.to_string(),
+            quote.to_string(),
+            Some(TimeConstraints {
+                block_time,
+                valid_timeframe,
+            }),
+        )
+    };
+
+    // the oldest allowed update time is accepted, one second older is not
+    query("terra123123", "uusd", 200, 100).unwrap();
+    assert_eq!(
+        query("terra123123", "uusd", 201, 100),
+        Err(PriceError::Stale {
+            asset: "terra123123".to_string(),
+            last_updated: 100,
+            min_allowed: 101,
+        })
+    );
+
+    // a timeframe longer than the block time does not underflow
+    query("terra123123", "uusd", 200, 500).unwrap();
+    query("terra123123", "uusd", 200, u64::MAX).unwrap();
+
+    // a price cannot be updated after the current block
+    assert_eq!(
+        query("terra123123", "uusd", 199, u64::MAX),
+        Err(PriceError::FutureTimestamp {
+            asset: "uusd".to_string(),
+            last_updated: 200,
+            block_time: 199,
+        })
+    );
+
+    // u64::MAX marks a price which is never outdated
+    query("uusd", "terra123123", 150, 50).unwrap();
+    assert_eq!(
+        query("uusd", "terra123123", 151, 50),
+        Err(PriceError::Stale {
+            asset: "terra123123".to_string(),
+            last_updated: 100,
+            min_allowed: 101,
+        })
+    );
+
+    assert_eq!(
+        query("terra123123", "ukrw", 200, 100),
+        Err(PriceError::Missing {
+            base: "terra123123".to_string(),
+            quote: "ukrw".to_string(),
+        })
+    );
+}
+
+/// Answers every query with the same result
+struct FixedQuerier(QuerierResult);
+
+impl Querier for FixedQuerier {
+    fn raw_query(&self, _bin_request: &[u8]) -> QuerierResult {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn oracle_price_querier_errors() {
+    let query = |result: QuerierResult| {
+        let deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: FixedQuerier(result),
+        };
+        query_price(
+            deps.as_ref(),
+            Addr::unchecked("oracle"),
+            "terra123123".to_string(),
+            "uusd".to_string(),
+            None,
+        )
+    };
+    let missing = Err(PriceError::Missing {
+        base: "terra123123".to_string(),
+        quote: "uusd".to_string(),
+    });
+
+    assert_eq!(
+        query(SystemResult::Ok(ContractResult::Err(
+            price_not_found("terra123123").to_string()
+        ))),
+        missing
+    );
+    assert_eq!(
+        query(SystemResult::Ok(ContractResult::Err(
+            price_not_found("uusd").to_string()
+        ))),
+        missing
+    );
+
+    // anything else the oracle cannot find is not a missing price
+    for error in [
+        StdError::not_found("moneymarket_oracle::state::Config"),
+        price_not_found("ukrw"),
+    ] {
+        match query(SystemResult::Ok(ContractResult::Err(error.to_string()))) {
+            Err(PriceError::Std(StdError::GenericErr { msg })) => {
+                assert!(msg.starts_with("Querier contract error"), "{}", msg)
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    // neither is an unreachable oracle
+    match query(SystemResult::Err(SystemError::NoSuchContract {
+        addr: "oracle".to_string(),
+    })) {
+        Err(PriceError::Std(StdError::GenericErr { msg })) => {
+            assert!(msg.starts_with("Querier system error"), "{}", msg)
+        }
         _ => panic!("DO NOT ENTER HERE"),
     }
 }
@@ -300,3 +456,296 @@ fn token_math_invalid_token_2() {
 
     let _ = tokens_1_raw.sub(tokens_2_raw);
 }
+
+#[test]
+fn funds_policies() {
+    let uusd = Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(100u128),
+    };
+    let ukrw = Coin {
+        denom: "ukrw".to_string(),
+        amount: Uint128::from(100u128),
+    };
+
+    let funds = vec![uusd, ukrw];
+
+    assert_eq!(assert_no_funds(&[]), Ok(()));
+    assert_eq!(
+        assert_no_funds(&funds[..1]),
+        Err(FundsError::UnexpectedFunds {})
+    );
+
+    assert_eq!(assert_one_denom(&[]), Err(FundsError::NoFunds {}));
+    assert_eq!(assert_one_denom(&funds[..1]), Ok(&funds[0]));
+    assert_eq!(
+        assert_one_denom(&funds),
+        Err(FundsError::MultipleDenoms { count: 2 })
+    );
+
+    assert_eq!(assert_allowed_denoms(&[], &["uusd"]), Ok(()));
+    assert_eq!(assert_allowed_denoms(&funds, &["uusd", "ukrw"]), Ok(()));
+    assert_eq!(
+        assert_allowed_denoms(&funds, &["uusd"]),
+        Err(FundsError::DenomNotAllowed {
+            denom: "ukrw".to_string()
+        })
+    );
+}
+
+#[test]
+fn price_guard() {
+    let mut deps = mock_dependencies(&[]);
+    let base = "terra123123".to_string();
+    let quote = "uusd".to_string();
+
+    // disabled guard accepts any move and keeps no reference
+    guard_price(deps.as_mut().storage, 100, &base, &quote, Decimal256::one()).unwrap();
+    guard_price(
+        deps.as_mut().storage,
+        100,
+        &base,
+        &quote,
+        Decimal256::percent(1),
+    )
+    .unwrap();
+    assert_eq!(
+        read_price_reference(deps.as_ref().storage, &base, &quote).unwrap(),
+        None
+    );
+
+    assert_eq!(
+        store_price_guard_config(
+            deps.as_mut().storage,
+            &PriceGuardConfig {
+                max_change: Decimal256::percent(10),
+                window: 0,
+            },
+        ),
+        Err(PriceGuardError::InvalidWindow {})
+    );
+    for max_change in [Decimal256::zero(), Decimal256::percent(101)] {
+        assert_eq!(
+            store_price_guard_config(
+                deps.as_mut().storage,
+                &PriceGuardConfig {
+                    max_change,
+                    window: 10,
+                },
+            ),
+            Err(PriceGuardError::InvalidMaxChange { max_change })
+        );
+    }
+    store_price_guard_config(
+        deps.as_mut().storage,
+        &PriceGuardConfig {
+            max_change: Decimal256::percent(10),
+            window: 10,
+        },
+    )
+    .unwrap();
+
+    // first rate becomes the reference
+    guard_price(deps.as_mut().storage, 100, &base, &quote, Decimal256::one()).unwrap();
+
+    // moves within the band do not advance the reference inside the window
+    guard_price(
+        deps.as_mut().storage,
+        101,
+        &base,
+        &quote,
+        Decimal256::percent(110),
+    )
+    .unwrap();
+    assert_eq!(
+        guard_price(
+            deps.as_mut().storage,
+            102,
+            &base,
+            &quote,
+            Decimal256::percent(120)
+        ),
+        Err(PriceGuardError::Tripped {
+            base: base.clone(),
+            quote: quote.clone(),
+            reference_rate: Decimal256::one(),
+            rate: Decimal256::percent(120),
+        })
+    );
+    guard_price(
+        deps.as_mut().storage,
+        102,
+        &base,
+        &quote,
+        Decimal256::percent(90),
+    )
+    .unwrap();
+    assert_eq!(
+        read_price_reference(deps.as_ref().storage, &base, &quote).unwrap(),
+        Some(PriceReference {
+            rate: Decimal256::one(),
+            height: 100,
+        })
+    );
+
+    // the band widens with the elapsed windows, and the reference moves on
+    guard_price(
+        deps.as_mut().storage,
+        120,
+        &base,
+        &quote,
+        Decimal256::percent(120),
+    )
+    .unwrap();
+    assert_eq!(
+        read_price_reference(deps.as_ref().storage, &base, &quote).unwrap(),
+        Some(PriceReference {
+            rate: Decimal256::percent(120),
+            height: 120,
+        })
+    );
+
+    // other pairs are guarded independently
+    guard_price(
+        deps.as_mut().storage,
+        120,
+        &base,
+        "ukrw",
+        Decimal256::percent(1),
+    )
+    .unwrap();
+
+    // disabling the guard restores unguarded prices and drops the references
+    remove_price_guard_config(deps.as_mut().storage);
+    assert_eq!(
+        read_price_guard_config(deps.as_ref().storage).unwrap(),
+        None
+    );
+    guard_price(
+        deps.as_mut().storage,
+        121,
+        &base,
+        &quote,
+        Decimal256::percent(1000),
+    )
+    .unwrap();
+    assert_eq!(
+        read_price_reference(deps.as_ref().storage, &base, &quote).unwrap(),
+        None
+    );
+    assert_eq!(
+        read_price_reference(deps.as_ref().storage, &base, "ukrw").unwrap(),
+        None
+    );
+
+    // enabling it again anchors on the next price
+    store_price_guard_config(
+        deps.as_mut().storage,
+        &PriceGuardConfig {
+            max_change: Decimal256::percent(10),
+            window: 10,
+        },
+    )
+    .unwrap();
+    guard_price(
+        deps.as_mut().storage,
+        122,
+        &base,
+        &quote,
+        Decimal256::percent(1000),
+    )
+    .unwrap();
+    assert_eq!(
+        read_price_reference(deps.as_ref().storage, &base, &quote).unwrap(),
+        Some(PriceReference {
+            rate: Decimal256::percent(1000),
+            height: 122,
+        })
+    );
+}
+
+#[test]
+fn price_guard_oracle() {
+    let mut deps = mock_dependencies(&[]);
+    let oracle = Addr::unchecked("oracle");
+    let base = "terra123123".to_string();
+    let quote = "uusd".to_string();
+
+    store_price_guard_config(
+        deps.as_mut().storage,
+        &PriceGuardConfig {
+            max_change: Decimal256::percent(5),
+            window: 10,
+        },
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &(base.clone(), quote.clone()),
+        &(Decimal256::one(), 100, 100),
+    )]);
+    query_guarded_price(
+        deps.as_mut(),
+        oracle.clone(),
+        base.clone(),
+        quote.clone(),
+        None,
+        100,
+    )
+    .unwrap();
+
+    // the oracle moves out of the band
+    deps.querier.with_oracle_price(&[(
+        &(base.clone(), quote.clone()),
+        &(Decimal256::percent(80), 105, 105),
+    )]);
+    match query_guarded_price(
+        deps.as_mut(),
+        oracle.clone(),
+        base.clone(),
+        quote.clone(),
+        None,
+        105,
+    ) {
+        Err(PriceGuardError::Tripped { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query_price_guard(
+        deps.as_ref(),
+        oracle.clone(),
+        base.clone(),
+        quote.clone(),
+        105,
+    )
+    .unwrap();
+    assert!(res.tripped);
+    assert_eq!(res.rate, Decimal256::percent(80));
+    assert_eq!(
+        res.reference,
+        Some(PriceReference {
+            rate: Decimal256::one(),
+            height: 100,
+        })
+    );
+
+    // re-anchoring resumes the guarded queries
+    reset_price_reference(
+        deps.as_mut(),
+        oracle.clone(),
+        base.clone(),
+        quote.clone(),
+        106,
+    )
+    .unwrap();
+    let res = query_price_guard(
+        deps.as_ref(),
+        oracle.clone(),
+        base.clone(),
+        quote.clone(),
+        106,
+    )
+    .unwrap();
+    assert!(!res.tripped);
+    query_guarded_price(deps.as_mut(), oracle, base, quote, None, 106).unwrap();
+}
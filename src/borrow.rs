@@ -0,0 +1,591 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Uint128, WasmMsg,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::assert_market_open;
+use crate::deposit::{
+    compute_available_liquidity, compute_exchange_rate, compute_exchange_rate_raw,
+};
+use crate::error::ContractError;
+use crate::markets::{load_market, market_attributes, query_load_market};
+use crate::querier::{
+    query_borrow_limit, query_borrow_rate, query_overseer_config, query_target_deposit_rate,
+};
+use crate::state::{
+    read_all_markets, read_borrower_info, read_borrower_infos, read_config, read_denom_market,
+    read_market_state, read_state, store_borrower_info, store_market_state, store_state,
+    BorrowerInfo, Config, Market, State,
+};
+use crate::withdrawal_queue::settle_withdrawal_queue;
+
+use moneymarket::common::{assert_one_denom, FundsError};
+use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
+use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse as OverseerConfigResponse};
+use moneymarket::querier::{query_price, query_supply, TimeConstraints};
+
+/// Distributor (ANC faucet) contract message
+/// used to pay out the claimed borrower rewards
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FaucetExecuteMsg {
+    Spend { recipient: String, amount: Uint128 },
+}
+
+pub fn borrow_stable(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrow_amount: Uint256,
+    to: Option<Addr>,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let market = load_market(deps.storage, &config, denom)?;
+    assert_market_open(deps.storage, &market)?;
+
+    // Cannot borrow zero amount
+    if borrow_amount.is_zero() {
+        return Err(ContractError::ZeroBorrow {
+            denom: market.stable_denom,
+        });
+    }
+
+    let mut state: State = read_market_state(deps.storage, &market)?;
+
+    let borrower = info.sender;
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &market, &borrower_raw);
+
+    // Compute interest and reward before updating anc_emission_rate
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &market,
+        &mut state,
+        env.block.height,
+        None,
+    )?;
+    compute_borrower_interest(&state, &mut liability);
+
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    let overseer = deps.api.addr_humanize(&config.overseer_contract)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps.as_ref(),
+        overseer,
+        borrower.clone(),
+        Some(env.block.time.seconds()),
+    )?;
+
+    // The borrow limit is in the primary denom and covers the loans of all markets
+    let loans_value = compute_loans_value(
+        deps.as_ref(),
+        &env,
+        &config,
+        &borrower_raw,
+        &market,
+        borrow_amount + liability.loan_amount,
+    )?;
+    if borrow_limit_res.borrow_limit < loans_value {
+        return Err(ContractError::BorrowExceedsLimit {
+            denom: config.stable_denom,
+            loan_amount: loans_value,
+            borrow_limit: borrow_limit_res.borrow_limit,
+        });
+    }
+
+    let current_balance = market
+        .stable_asset()
+        .query_balance(deps.as_ref(), env.contract.address)?;
+
+    // Assert borrow amount
+    assert_max_borrow_factor(&config, &market, &state, current_balance, borrow_amount)?;
+
+    liability.loan_amount += borrow_amount;
+    state.total_liabilities += Decimal256::from_uint256(borrow_amount);
+    store_market_state(deps.storage, &market, &state)?;
+    store_borrower_info(deps.storage, &market, &borrower_raw, &liability)?;
+
+    let asset = market.stable_asset();
+    Ok(Response::new()
+        .add_message(asset.transfer_msg(
+            &to.unwrap_or_else(|| borrower.clone()),
+            asset.deduct_tax(deps.as_ref(), borrow_amount)?,
+        )?)
+        .add_attributes(vec![
+            attr("action", "borrow_stable"),
+            attr("borrower", borrower),
+            attr("borrow_amount", borrow_amount),
+        ])
+        .add_attributes(market_attributes(&market)))
+}
+
+pub fn repay_stable_from_liquidation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    prev_balance: Uint256,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if config.overseer_contract != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let market = load_market(deps.storage, &config, denom)?;
+    let cur_balance: Uint256 = market
+        .stable_asset()
+        .query_balance(deps.as_ref(), env.contract.address.clone())?;
+
+    if cur_balance < prev_balance {
+        return Err(ContractError::InvalidLiquidationRepay {
+            denom: market.stable_denom,
+            prev_balance,
+            current_balance: cur_balance,
+        });
+    }
+
+    repay_stable_amount(deps, env, market, borrower, cur_balance - prev_balance)
+}
+
+/// The repaid market is the one of the sent coins
+pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // Nothing sent; rejected as a zero repay of the primary market
+    if info.funds.is_empty() {
+        return repay_stable_amount(
+            deps,
+            env,
+            config.primary_market(),
+            info.sender,
+            Uint256::zero(),
+        );
+    }
+
+    let repay = assert_one_denom(&info.funds)?;
+    let market = read_denom_market(deps.storage, &config, &repay.denom)?
+        .filter(|market| market.stable_asset().is_native())
+        .ok_or_else(|| FundsError::DenomNotAllowed {
+            denom: repay.denom.clone(),
+        })?;
+    let amount = Uint256::from(repay.amount);
+
+    repay_stable_amount(deps, env, market, info.sender, amount)
+}
+
+/// Repay the loan of the borrower with the stables already received;
+/// the excess is sent back
+pub fn repay_stable_amount(
+    mut deps: DepsMut,
+    env: Env,
+    market: Market,
+    borrower: Addr,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // Cannot repay zero amount
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay {
+            denom: market.stable_denom,
+        });
+    }
+
+    let mut state: State = read_market_state(deps.storage, &market)?;
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &market, &borrower_raw);
+
+    // Compute interest
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &market,
+        &mut state,
+        env.block.height,
+        Some(amount),
+    )?;
+    compute_borrower_interest(&state, &mut liability);
+
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    let repay_amount: Uint256;
+    let mut refund_amount = Uint256::zero();
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if liability.loan_amount < amount {
+        repay_amount = liability.loan_amount;
+        refund_amount = amount - repay_amount;
+        liability.loan_amount = Uint256::zero();
+
+        // Payback left repay amount to sender
+        let asset = market.stable_asset();
+        messages
+            .push(asset.transfer_msg(&borrower, asset.deduct_tax(deps.as_ref(), refund_amount)?)?);
+    } else {
+        repay_amount = amount;
+        liability.loan_amount = liability.loan_amount - repay_amount;
+    }
+
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(repay_amount);
+
+    // Serve the withdrawal queue with the repaid liquidity
+    let exchange_rate =
+        compute_exchange_rate(deps.as_ref(), &config, &market, &state, Some(refund_amount))?;
+    let current_balance = market
+        .stable_asset()
+        .query_balance(deps.as_ref(), env.contract.address)?;
+    let available = compute_available_liquidity(&state, current_balance - refund_amount);
+    let (settle_messages, settle_attributes) =
+        settle_withdrawal_queue(deps.branch(), &market, &mut state, exchange_rate, available)?;
+
+    store_borrower_info(deps.storage, &market, &borrower_raw, &liability)?;
+    store_market_state(deps.storage, &market, &state)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_messages(settle_messages)
+        .add_attributes(vec![
+            attr("action", "repay_stable"),
+            attr("borrower", borrower),
+            attr("repay_amount", repay_amount),
+        ])
+        .add_attributes(market_attributes(&market))
+        .add_attributes(settle_attributes))
+}
+
+pub fn claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    // ANC rewards only go to the borrowers of the primary market
+    let config: Config = read_config(deps.storage)?;
+    let market = config.primary_market();
+    let mut state: State = read_state(deps.storage)?;
+
+    let borrower = info.sender;
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &market, &borrower_raw);
+
+    // Compute interest and reward before claiming the rewards
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &market,
+        &mut state,
+        env.block.height,
+        None,
+    )?;
+    compute_borrower_interest(&state, &mut liability);
+
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    let claim_amount = liability.pending_rewards * Uint256::one();
+    liability.pending_rewards = liability.pending_rewards - Decimal256::from_uint256(claim_amount);
+
+    store_state(deps.storage, &state)?;
+    store_borrower_info(deps.storage, &market, &borrower_raw, &liability)?;
+
+    let messages: Vec<CosmosMsg> = if !claim_amount.is_zero() {
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps
+                .api
+                .addr_humanize(&config.distributor_contract)?
+                .to_string(),
+            funds: vec![],
+            msg: to_binary(&FaucetExecuteMsg::Spend {
+                recipient: to.unwrap_or_else(|| borrower.clone()).to_string(),
+                amount: claim_amount.into(),
+            })?,
+        })]
+    } else {
+        vec![]
+    };
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "claim_rewards"),
+        attr("borrower", borrower),
+        attr("claim_amount", claim_amount),
+    ]))
+}
+
+/// Value of the loans of the borrower in all markets, in the primary
+/// denom at the oracle price; the loan of `market` is taken as
+/// `loan_amount`, the others with the interest accrued to their market
+/// state. The oracle is only queried for the added markets with a loan
+fn compute_loans_value(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    borrower: &CanonicalAddr,
+    market: &Market,
+    loan_amount: Uint256,
+) -> Result<Uint256, ContractError> {
+    let mut markets = vec![config.primary_market()];
+    markets.extend(read_all_markets(deps.storage)?);
+
+    let mut overseer_config: Option<OverseerConfigResponse> = None;
+    let mut loans_value = Uint256::zero();
+    for loan_market in markets {
+        let loan_amount = if loan_market.stable_denom == market.stable_denom {
+            loan_amount
+        } else {
+            let state: State = read_market_state(deps.storage, &loan_market)?;
+            let mut liability: BorrowerInfo =
+                read_borrower_info(deps.storage, &loan_market, borrower);
+            compute_borrower_interest(&state, &mut liability);
+            liability.loan_amount
+        };
+
+        if loan_amount.is_zero() {
+            continue;
+        }
+
+        if loan_market.primary {
+            loans_value += loan_amount;
+            continue;
+        }
+
+        if overseer_config.is_none() {
+            overseer_config = Some(query_overseer_config(
+                deps,
+                deps.api.addr_humanize(&config.overseer_contract)?,
+            )?);
+        }
+        let overseer_config = overseer_config.as_ref().unwrap();
+
+        let price = query_price(
+            deps,
+            deps.api.addr_validate(&overseer_config.oracle_contract)?,
+            loan_market.stable_denom,
+            config.stable_denom.clone(),
+            Some(TimeConstraints {
+                block_time: env.block.time.seconds(),
+                valid_timeframe: overseer_config.price_timeframe,
+            }),
+        )
+        .map_err(StdError::from)?;
+        loans_value += loan_amount * price.rate;
+    }
+
+    Ok(loans_value)
+}
+
+fn assert_max_borrow_factor(
+    config: &Config,
+    market: &Market,
+    state: &State,
+    current_balance: Uint256,
+    borrow_amount: Uint256,
+) -> Result<(), ContractError> {
+    let current_balance = Decimal256::from_uint256(current_balance);
+    let borrow_amount = Decimal256::from_uint256(borrow_amount);
+
+    // Assert max borrow factor
+    let max_borrow_amount = (current_balance + state.total_liabilities - state.total_reserves)
+        * config.max_borrow_factor;
+    if state.total_liabilities + borrow_amount > max_borrow_amount {
+        return Err(ContractError::MaxBorrowFactorReached {
+            denom: market.stable_denom.clone(),
+            total_liabilities: (state.total_liabilities + borrow_amount) * Uint256::one(),
+            max_borrow_amount: max_borrow_amount * Uint256::one(),
+        });
+    }
+
+    // Assert available balance
+    if borrow_amount + state.total_reserves > current_balance {
+        let available = if current_balance > state.total_reserves {
+            current_balance - state.total_reserves
+        } else {
+            Decimal256::zero()
+        };
+
+        return Err(ContractError::NoStableAvailable {
+            denom: market.stable_denom.clone(),
+            requested: borrow_amount * Uint256::one(),
+            available: available * Uint256::one(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Compute interest and update state
+/// total liabilities and total reserves
+pub fn compute_interest(
+    deps: Deps,
+    config: &Config,
+    market: &Market,
+    state: &mut State,
+    block_height: u64,
+    deposit_amount: Option<Uint256>,
+) -> StdResult<()> {
+    if state.last_interest_updated >= block_height {
+        return Ok(());
+    }
+
+    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&market.aterra_contract)?)?;
+    let balance: Uint256 = market
+        .stable_asset()
+        .query_balance(deps, deps.api.addr_humanize(&config.contract_addr)?)?
+        - deposit_amount.unwrap_or_else(Uint256::zero);
+
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps,
+        deps.api.addr_humanize(&config.interest_model)?,
+        balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+
+    let target_deposit_rate: Decimal256 =
+        query_target_deposit_rate(deps, deps.api.addr_humanize(&config.overseer_contract)?)?;
+
+    compute_interest_raw(
+        state,
+        block_height,
+        balance,
+        aterra_supply,
+        borrow_rate_res.rate,
+        target_deposit_rate,
+    );
+
+    Ok(())
+}
+
+pub fn compute_interest_raw(
+    state: &mut State,
+    block_height: u64,
+    balance: Uint256,
+    aterra_supply: Uint256,
+    borrow_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+) {
+    if state.last_interest_updated >= block_height {
+        return;
+    }
+
+    let passed_blocks = Decimal256::from_uint256(block_height - state.last_interest_updated);
+
+    let interest_factor: Decimal256 = passed_blocks * borrow_rate;
+    let interest_accrued: Decimal256 = state.total_liabilities * interest_factor;
+
+    state.global_interest_index =
+        state.global_interest_index * (Decimal256::one() + interest_factor);
+    state.total_liabilities += interest_accrued;
+
+    let mut exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
+    let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
+    let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_blocks;
+
+    if deposit_rate > target_deposit_rate {
+        // excess_deposit_rate(_per_block)
+        let excess_deposit_rate = deposit_rate - target_deposit_rate;
+        let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
+
+        // excess_deposits_now = prev_deposits * excess_deposit_rate(_per_block) * blocks
+        let excess_deposits = prev_deposits * excess_deposit_rate * passed_blocks;
+
+        state.total_reserves += Decimal256::from_uint256(excess_deposits);
+        exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
+    }
+
+    state.prev_aterra_supply = aterra_supply;
+    state.prev_exchange_rate = exchange_rate;
+    state.last_interest_updated = block_height;
+}
+
+/// Compute distributed reward and update global reward index
+pub fn compute_reward(state: &mut State, block_height: u64) {
+    if state.last_reward_updated >= block_height {
+        return;
+    }
+
+    let passed_blocks = Decimal256::from_uint256(block_height - state.last_reward_updated);
+    let reward_accrued = passed_blocks * state.anc_emission_rate;
+    let borrow_amount = state.total_liabilities / state.global_interest_index;
+
+    if !reward_accrued.is_zero() && !borrow_amount.is_zero() {
+        state.global_reward_index += reward_accrued / borrow_amount;
+    }
+
+    state.last_reward_updated = block_height;
+}
+
+/// Compute new interest and apply to liability
+pub(crate) fn compute_borrower_interest(state: &State, liability: &mut BorrowerInfo) {
+    liability.loan_amount =
+        liability.loan_amount * state.global_interest_index / liability.interest_index;
+    liability.interest_index = state.global_interest_index;
+}
+
+/// Compute distributed reward and update global index
+pub(crate) fn compute_borrower_reward(state: &State, liability: &mut BorrowerInfo) {
+    liability.pending_rewards += Decimal256::from_uint256(liability.loan_amount)
+        / state.global_interest_index
+        * (state.global_reward_index - liability.reward_index);
+    liability.reward_index = state.global_reward_index;
+}
+
+pub fn query_borrower_info(
+    deps: Deps,
+    borrower: Addr,
+    block_height: Option<u64>,
+    denom: Option<String>,
+) -> StdResult<BorrowerInfoResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(
+        deps.storage,
+        &market,
+        &deps.api.addr_canonicalize(borrower.as_str())?,
+    );
+
+    if let Some(block_height) = block_height {
+        let mut state: State = read_market_state(deps.storage, &market)?;
+
+        compute_interest(deps, &config, &market, &mut state, block_height, None)?;
+        compute_reward(&mut state, block_height);
+
+        compute_borrower_interest(&state, &mut borrower_info);
+        compute_borrower_reward(&state, &mut borrower_info);
+    }
+
+    Ok(BorrowerInfoResponse {
+        borrower: borrower.to_string(),
+        interest_index: borrower_info.interest_index,
+        reward_index: borrower_info.reward_index,
+        loan_amount: borrower_info.loan_amount,
+        pending_rewards: borrower_info.pending_rewards,
+    })
+}
+
+pub fn query_borrower_infos(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+    denom: Option<String>,
+) -> StdResult<BorrowerInfosResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let borrower_infos: Vec<BorrowerInfoResponse> =
+        read_borrower_infos(deps, &market, start_after, limit)?;
+    Ok(BorrowerInfosResponse { borrower_infos })
+}
@@ -1,40 +1,97 @@
-// src/contract.rs
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    generic_err, log, to_binary, Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse,
-    Querier, StdResult, Storage, Uint128,
+    attr, from_binary, to_binary, Addr, Attribute, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut,
+    Env, MessageInfo, Reply, Response, StdError, StdResult, Storage, Uint128,
 };
 
-use crate::msg::{BalanceResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg};
-use crate::state::{balance_get, balance_set, config_get, config_set, Config};
-
-use crate::deposit::{compute_exchange_rate_raw, deposit_stable, redeem_stable};
+use crate::borrow::{
+    borrow_stable, claim_rewards, compute_interest, compute_interest_raw, compute_reward,
+    query_borrower_info, query_borrower_infos, repay_stable, repay_stable_amount,
+    repay_stable_from_liquidation,
+};
+use crate::denom::aterra_metadata;
+use crate::deposit::{
+    compute_available_liquidity, compute_exchange_rate_raw, deposit_stable, query_simulate_deposit,
+    query_simulate_redeem, redeem_exact_stable, redeem_stable,
+};
+use crate::error::ContractError;
+use crate::history::{query_exchange_rate_history, record_exchange_rate_snapshot};
+use crate::markets::{
+    add_market, instantiate_aterra_msg, query_load_market, query_market, query_markets,
+    register_market_aterra, remove_market,
+};
+use crate::querier::{query_anc_emission_rate, query_borrow_rate, query_target_deposit_rate};
+use crate::response::parse_instantiate_contract_address;
+use crate::state::{
+    is_initial_deposit_pending, read_all_markets, read_config, read_denom_market,
+    read_market_by_aterra, read_market_state, read_state, remove_pending_initial_deposit,
+    store_config, store_market_state, store_pending_initial_deposit, store_state, Config, Market,
+    State,
+};
+use crate::withdrawal_queue::{
+    cancel_withdrawal, query_withdrawal_request, query_withdrawal_requests, queue_redeem,
+    settle_withdrawal_queue,
+};
 
-use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
-use moneymarket::common::optional_addr_validate;
+use cw20::Cw20ReceiveMsg;
+use moneymarket::asset::AssetInfo;
+use moneymarket::common::{
+    assert_no_funds, assert_one_denom, claim_ownership, drop_ownership_proposal,
+    optional_addr_validate, propose_new_owner, FundsError,
+};
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{
-    ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StateResponse,
+    ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    QueryMsg, StateResponse,
 };
+use moneymarket::querier::query_supply;
+
+pub const INITIAL_DEPOSIT_AMOUNT: u128 = 1000000;
+
+/// Sub message id of the aterra token instantiation
+pub const INSTANTIATE_ATERRA_REPLY_ID: u64 = 1;
+/// Sub message id of the aterra token instantiation of an added market
+pub const INSTANTIATE_MARKET_ATERRA_REPLY_ID: u64 = 2;
 
-pub fn init<S: Storage, A: Api, Q: Querier>(
-    deps: &mut Extern<S, A, Q>,
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
     env: Env,
-    msg: InitMsg,
-) -> StdResult<InitResponse> {
-    // Initial balances
-    for row in msg.initial_balances {
-        let address = deps.api.canonical_address(&row.address)?;
-        balance_set(&mut deps.storage, &address, &row.amount)?;
-    }
-    config_set(
-        &mut deps.storage,
-        &Config {
-            name: msg.name,
-            symbol: msg.symbol,
-            owner: env.message.sender,
-        },
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    // The initial deposit of a native primary market has to be sent with the
+    // message; a cw20 one is sent with the InitialDeposit hook, as the
+    // contract address is unknown to approve a transfer beforehand
+    let stable_asset = AssetInfo::from_denom(&msg.stable_denom);
+    if stable_asset.is_native() {
+        let initial_deposit = info
+            .funds
+            .iter()
+            .find(|c| c.denom == msg.stable_denom)
+            .map(|c| c.amount)
+            .unwrap_or_else(Uint128::zero);
+
+        if initial_deposit != Uint128::from(INITIAL_DEPOSIT_AMOUNT) {
+            return Err(ContractError::InitialFundsNotDeposited {
+                denom: msg.stable_denom,
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            });
+        }
+    } else {
+        assert_no_funds(&info.funds)?;
+        store_pending_initial_deposit(deps.storage)?;
+    }
+
+    assert_max_borrow_factor_range(msg.max_borrow_factor)?;
+    let (aterra_name, aterra_symbol) = aterra_metadata(
+        deps.api,
+        &msg.stable_denom,
+        msg.aterra_name,
+        msg.aterra_symbol,
     )?;
 
     store_config(
@@ -63,41 +120,20 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: msg.anc_emission_rate,
-            prev_aterra_supply: Uint256::zero(),
+            prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
             prev_exchange_rate: Decimal256::one(),
         },
     )?;
 
-    Ok(
-        Response::new().add_submessages(vec![SubMsg::reply_on_success(
-            CosmosMsg::Wasm(WasmMsg::Instantiate {
-                admin: None,
-                code_id: msg.aterra_code_id,
-                funds: vec![],
-                label: "".to_string(),
-                msg: to_binary(&TokenInstantiateMsg {
-                    name: format!("Anchor Terra {}", msg.stable_denom[1..].to_uppercase()),
-                    symbol: format!(
-                        "a{}T",
-                        msg.stable_denom[1..(msg.stable_denom.len() - 1)].to_uppercase()
-                    ),
-                    decimals: 6u8,
-                    initial_balances: vec![Cw20Coin {
-                        address: env.contract.address.to_string(),
-                        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-                    }],
-                    mint: Some(MinterResponse {
-                        minter: env.contract.address.to_string(),
-                        cap: None,
-                    }),
-                })?,
-            }),
-            1,
-        )]),
-    )
+    Ok(Response::new().add_submessage(instantiate_aterra_msg(
+        &env,
+        msg.aterra_code_id,
+        aterra_name,
+        aterra_symbol,
+        INSTANTIATE_ATERRA_REPLY_ID,
+    )?))
 }
 
-
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -106,7 +142,93 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Receive(msg) => {
+            assert_no_funds(&info.funds)?;
+            receive_cw20(deps, env, info, msg)
+        }
+        ExecuteMsg::DepositStable {
+            recipient,
+            min_mint_amount,
+        } => {
+            let deposit = assert_one_denom(&info.funds)?.clone();
+            // cw20 stables are only deposited through the receive hook
+            if !AssetInfo::from_denom(&deposit.denom).is_native() {
+                return Err(FundsError::DenomNotAllowed {
+                    denom: deposit.denom,
+                }
+                .into());
+            }
+
+            let api = deps.api;
+            deposit_stable(
+                deps,
+                env,
+                info.sender,
+                deposit.denom,
+                Uint256::from(deposit.amount),
+                optional_addr_validate(api, recipient)?,
+                min_mint_amount,
+            )
+        }
+        ExecuteMsg::BorrowStable {
+            borrow_amount,
+            to,
+            denom,
+        } => {
+            assert_no_funds(&info.funds)?;
+            let api = deps.api;
+            borrow_stable(
+                deps,
+                env,
+                info,
+                borrow_amount,
+                optional_addr_validate(api, to)?,
+                denom,
+            )
+        }
+        ExecuteMsg::RepayStable {} => repay_stable(deps, env, info),
+        ExecuteMsg::RepayStableFromLiquidation {
+            borrower,
+            prev_balance,
+            denom,
+        } => {
+            assert_no_funds(&info.funds)?;
+            let api = deps.api;
+            repay_stable_from_liquidation(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                prev_balance,
+                denom,
+            )
+        }
+        ExecuteMsg::ClaimRewards { to } => {
+            assert_no_funds(&info.funds)?;
+            let api = deps.api;
+            claim_rewards(deps, env, info, optional_addr_validate(api, to)?)
+        }
+        ExecuteMsg::CancelWithdrawal { id, denom } => {
+            assert_no_funds(&info.funds)?;
+            cancel_withdrawal(deps, info, id, denom)
+        }
+        ExecuteMsg::ExecuteEpochOperations {
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            distributed_interest,
+        } => {
+            assert_no_funds(&info.funds)?;
+            execute_epoch_operations(
+                deps,
+                env,
+                info,
+                deposit_rate,
+                target_deposit_rate,
+                threshold_deposit_rate,
+                distributed_interest,
+            )
+        }
         ExecuteMsg::RegisterContracts {
             overseer_contract,
             interest_model,
@@ -114,9 +236,11 @@ pub fn execute(
             collector_contract,
             distributor_contract,
         } => {
+            assert_no_funds(&info.funds)?;
             let api = deps.api;
             register_contracts(
                 deps,
+                info,
                 api.addr_validate(&overseer_contract)?,
                 api.addr_validate(&interest_model)?,
                 api.addr_validate(&distribution_model)?,
@@ -125,66 +249,485 @@ pub fn execute(
             )
         }
         ExecuteMsg::UpdateConfig {
-            owner_addr,
+            max_borrow_factor,
             interest_model,
             distribution_model,
-            max_borrow_factor,
         } => {
+            assert_no_funds(&info.funds)?;
             let api = deps.api;
             update_config(
                 deps,
                 env,
                 info,
-                optional_addr_validate(api, owner_addr)?,
+                max_borrow_factor,
                 optional_addr_validate(api, interest_model)?,
                 optional_addr_validate(api, distribution_model)?,
-                max_borrow_factor,
             )
         }
-        ExecuteMsg::ExecuteEpochOperations {
-            deposit_rate,
-            target_deposit_rate,
-            threshold_deposit_rate,
-            distributed_interest,
-        } => execute_epoch_operations(
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            assert_no_funds(&info.funds)?;
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner_addr)?;
+            Ok(propose_new_owner(
+                deps,
+                &env,
+                &info,
+                &owner_addr,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::ClaimOwnership {} => {
+            assert_no_funds(&info.funds)?;
+            Ok(claim_ownership(deps, &env, &info, store_owner)?)
+        }
+        ExecuteMsg::RejectOwnershipProposal {} => {
+            assert_no_funds(&info.funds)?;
+            let config: Config = read_config(deps.storage)?;
+            let owner_addr = deps.api.addr_humanize(&config.owner_addr)?;
+            Ok(drop_ownership_proposal(deps, &info, &owner_addr)?)
+        }
+        ExecuteMsg::AddMarket {
+            stable_denom,
+            aterra_code_id,
+            aterra_name,
+            aterra_symbol,
+        } => add_market(
             deps,
             env,
             info,
-            deposit_rate,
+            stable_denom,
+            aterra_code_id,
+            aterra_name,
+            aterra_symbol,
+        ),
+        ExecuteMsg::RemoveMarket { stable_denom } => {
+            assert_no_funds(&info.funds)?;
+            remove_market(deps, env, info, stable_denom)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id != INSTANTIATE_ATERRA_REPLY_ID && msg.id != INSTANTIATE_MARKET_ATERRA_REPLY_ID {
+        return Err(ContractError::InvalidReplyId(msg.id));
+    }
+
+    // get new token's contract address
+    let res = msg.result.into_result().map_err(StdError::generic_err)?;
+    let data = res.data.ok_or_else(|| {
+        StdError::parse_err("MsgInstantiateContractResponse", "missing reply data")
+    })?;
+    let token_addr = deps
+        .api
+        .addr_validate(&parse_instantiate_contract_address(data.as_slice())?)?;
+
+    if msg.id == INSTANTIATE_MARKET_ATERRA_REPLY_ID {
+        return register_market_aterra(deps, token_addr);
+    }
+
+    register_aterra(deps, token_addr)
+}
+
+/// Store the aterra token address; it can only be set once,
+/// by the reply of the instantiate sub message
+pub fn register_aterra(deps: DepsMut, token_addr: Addr) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if config.aterra_contract != CanonicalAddr::from(vec![]) {
+        return Err(ContractError::AterraAlreadyRegistered {});
+    }
+
+    config.aterra_contract = deps.api.addr_canonicalize(token_addr.as_str())?;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![("aterra", token_addr)]))
+}
+
+/// Called by the overseer at every epoch; accrues interest with the
+/// distributed interest excluded, sweeps the reserves to the collector
+/// and updates the ANC emission rate from the distribution model.
+/// The added markets accrue their interest and sweep their reserves too
+pub fn execute_epoch_operations(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    distributed_interest: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if config.overseer_contract != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let market = config.primary_market();
+    assert_market_open(deps.storage, &market)?;
+
+    let mut state: State = read_state(deps.storage)?;
+
+    // Compute interest and reward before updating anc_emission_rate
+    let aterra_supply = query_supply(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.aterra_contract)?,
+    )?;
+    let balance: Uint256 = config.stable_asset().query_balance(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.contract_addr)?,
+    )? - distributed_interest;
+
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.interest_model)?,
+        balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+
+    compute_interest_raw(
+        &mut state,
+        env.block.height,
+        balance,
+        aterra_supply,
+        borrow_rate_res.rate,
+        target_deposit_rate,
+    );
+
+    // recompute prev_exchange_rate with distributed_interest
+    state.prev_exchange_rate =
+        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest);
+
+    compute_reward(&mut state, env.block.height);
+
+    // Send the whole part of total_reserves to the collector contract,
+    // only when there is enough balance
+    let total_reserves = state.total_reserves * Uint256::one();
+    let messages: Vec<CosmosMsg> = if !total_reserves.is_zero() && balance > total_reserves {
+        state.total_reserves = state.total_reserves - Decimal256::from_uint256(total_reserves);
+
+        let asset = config.stable_asset();
+        vec![asset.transfer_msg(
+            &deps.api.addr_humanize(&config.collector_contract)?,
+            asset.deduct_tax(deps.as_ref(), total_reserves)?,
+        )?]
+    } else {
+        vec![]
+    };
+
+    // Serve the withdrawal queue with the liquidity left after the reserve sweep
+    let swept_reserves = if messages.is_empty() {
+        Uint256::zero()
+    } else {
+        total_reserves
+    };
+    let available =
+        compute_available_liquidity(&state, balance + distributed_interest - swept_reserves);
+    let exchange_rate = state.prev_exchange_rate;
+    let (settle_messages, settle_attributes) =
+        settle_withdrawal_queue(deps.branch(), &market, &mut state, exchange_rate, available)?;
+
+    // Query updated anc_emission_rate
+    state.anc_emission_rate = query_anc_emission_rate(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.distribution_model)?,
+        deposit_rate,
+        target_deposit_rate,
+        threshold_deposit_rate,
+        state.anc_emission_rate,
+    )?
+    .emission_rate;
+
+    store_state(deps.storage, &state)?;
+    record_exchange_rate_snapshot(deps.storage, &env, &state)?;
+
+    let mut market_messages: Vec<CosmosMsg> = vec![];
+    let mut market_attributes: Vec<Attribute> = vec![];
+    for added_market in read_all_markets(deps.storage)? {
+        let (messages, attributes) = execute_market_epoch_operations(
+            deps.branch(),
+            &env,
+            &config,
+            &added_market,
             target_deposit_rate,
-            threshold_deposit_rate,
-            distributed_interest,
+        )?;
+        market_messages.extend(messages);
+        market_attributes.extend(attributes);
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_messages(settle_messages)
+        .add_messages(market_messages)
+        .add_attributes(vec![
+            attr("action", "execute_epoch_operations"),
+            attr("total_liabilities", state.total_liabilities.to_string()),
+            attr("prev_aterra_supply", state.prev_aterra_supply),
+            attr("prev_exchange_rate", state.prev_exchange_rate.to_string()),
+            attr("total_reserves", total_reserves),
+            attr("anc_emission_rate", state.anc_emission_rate.to_string()),
+        ])
+        .add_attributes(settle_attributes)
+        .add_attributes(market_attributes))
+}
+
+/// Epoch operations of an added market; its interest is accrued with the
+/// target deposit rate of the epoch, its reserves are swept to the
+/// collector and its withdrawal queue is served. The attributes of each
+/// market follow a `market` attribute with its denom
+fn execute_market_epoch_operations(
+    mut deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    market: &Market,
+    target_deposit_rate: Decimal256,
+) -> Result<(Vec<CosmosMsg>, Vec<Attribute>), ContractError> {
+    let mut state: State = read_market_state(deps.storage, market)?;
+
+    let aterra_supply = query_supply(
+        deps.as_ref(),
+        deps.api.addr_humanize(&market.aterra_contract)?,
+    )?;
+    let asset = market.stable_asset();
+    let balance: Uint256 = asset.query_balance(deps.as_ref(), env.contract.address.clone())?;
+
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.interest_model)?,
+        balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+
+    compute_interest_raw(
+        &mut state,
+        env.block.height,
+        balance,
+        aterra_supply,
+        borrow_rate_res.rate,
+        target_deposit_rate,
+    );
+
+    // Same sweep as the primary market
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut swept_reserves = Uint256::zero();
+    let total_reserves = state.total_reserves * Uint256::one();
+    if !total_reserves.is_zero() && balance > total_reserves {
+        state.total_reserves = state.total_reserves - Decimal256::from_uint256(total_reserves);
+        swept_reserves = total_reserves;
+
+        messages.push(asset.transfer_msg(
+            &deps.api.addr_humanize(&config.collector_contract)?,
+            asset.deduct_tax(deps.as_ref(), total_reserves)?,
+        )?);
+    }
+
+    let available = compute_available_liquidity(&state, balance - swept_reserves);
+    let exchange_rate = state.prev_exchange_rate;
+    let (settle_messages, settle_attributes) =
+        settle_withdrawal_queue(deps.branch(), market, &mut state, exchange_rate, available)?;
+    messages.extend(settle_messages);
+
+    store_market_state(deps.storage, market, &state)?;
+
+    let mut attributes = vec![
+        attr("market", market.stable_denom.clone()),
+        attr(
+            "market_total_liabilities",
+            state.total_liabilities.to_string(),
         ),
-        ExecuteMsg::DepositStable {} => deposit_stable(deps, env, info),
-        // ExecuteMsg::BorrowStable { borrow_amount, to } => {
-        //     let api = deps.api;
-        //     borrow_stable(
-        //         deps,
-        //         env,
-        //         info,
-        //         borrow_amount,
-        //         optional_addr_validate(api, to)?,
-        //     )
-        // }
-        ExecuteMsg::RepayStable {} => repay_stable(deps, env, info),
-        ExecuteMsg::RepayStableFromLiquidation {
-            borrower,
-            prev_balance,
-        } => {
-            let api = deps.api;
-            repay_stable_from_liquidation(
-                deps,
-                env,
-                info,
-                api.addr_validate(&borrower)?,
-                prev_balance,
-            )
+        attr(
+            "market_prev_exchange_rate",
+            state.prev_exchange_rate.to_string(),
+        ),
+        attr("market_total_reserves", swept_reserves),
+    ];
+    attributes.extend(
+        settle_attributes
+            .into_iter()
+            .map(|attribute| attr(format!("market_{}", attribute.key), attribute.value)),
+    );
+
+    Ok((messages, attributes))
+}
+
+/// Register the contracts the market depends on; only the owner
+/// can do it, and only once right after the instantiation
+pub fn register_contracts(
+    deps: DepsMut,
+    info: MessageInfo,
+    overseer_contract: Addr,
+    interest_model: Addr,
+    distribution_model: Addr,
+    collector_contract: Addr,
+    distributor_contract: Addr,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !config.overseer_contract.is_empty()
+        || !config.interest_model.is_empty()
+        || !config.distribution_model.is_empty()
+        || !config.collector_contract.is_empty()
+        || !config.distributor_contract.is_empty()
+    {
+        return Err(ContractError::ContractsAlreadyRegistered {});
+    }
+
+    config.overseer_contract = deps.api.addr_canonicalize(overseer_contract.as_str())?;
+    config.interest_model = deps.api.addr_canonicalize(interest_model.as_str())?;
+    config.distribution_model = deps.api.addr_canonicalize(distribution_model.as_str())?;
+    config.collector_contract = deps.api.addr_canonicalize(collector_contract.as_str())?;
+    config.distributor_contract = deps.api.addr_canonicalize(distributor_contract.as_str())?;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_contracts"),
+        attr("overseer_contract", overseer_contract),
+        attr("interest_model", interest_model),
+        attr("distribution_model", distribution_model),
+        attr("collector_contract", collector_contract),
+        attr("distributor_contract", distributor_contract),
+    ]))
+}
+
+/// Update the owner configurable fields; the interest accrued so far
+/// is computed with the previous interest model before it is replaced
+pub fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_borrow_factor: Option<Decimal256>,
+    interest_model: Option<Addr>,
+    distribution_model: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the models are set by the one-shot registration, which a model set
+    // beforehand would block
+    if (interest_model.is_some() || distribution_model.is_some())
+        && config.overseer_contract.is_empty()
+    {
+        return Err(ContractError::ContractsNotRegistered {});
+    }
+
+    let mut attributes = vec![attr("action", "update_config")];
+
+    if let Some(max_borrow_factor) = max_borrow_factor {
+        assert_max_borrow_factor_range(max_borrow_factor)?;
+        attributes.push(attr(
+            "prev_max_borrow_factor",
+            config.max_borrow_factor.to_string(),
+        ));
+        attributes.push(attr("max_borrow_factor", max_borrow_factor.to_string()));
+        config.max_borrow_factor = max_borrow_factor;
+    }
+
+    if let Some(interest_model) = interest_model {
+        // accrue the interest of every market with the previous interest model
+        let mut markets = vec![config.primary_market()];
+        markets.extend(read_all_markets(deps.storage)?);
+        for market in markets {
+            let mut state: State = read_market_state(deps.storage, &market)?;
+            compute_interest(
+                deps.as_ref(),
+                &config,
+                &market,
+                &mut state,
+                env.block.height,
+                None,
+            )?;
+            store_market_state(deps.storage, &market, &state)?;
         }
-        // ExecuteMsg::ClaimRewards { to } => {
-        //     let api = deps.api;
-        //     claim_rewards(deps, env, info, optional_addr_validate(api, to)?)
-        // }
+
+        attributes.push(attr(
+            "prev_interest_model",
+            humanize_or_empty(deps.as_ref(), &config.interest_model)?,
+        ));
+        attributes.push(attr("interest_model", interest_model.as_str()));
+        config.interest_model = deps.api.addr_canonicalize(interest_model.as_str())?;
+    }
+
+    if let Some(distribution_model) = distribution_model {
+        attributes.push(attr(
+            "prev_distribution_model",
+            humanize_or_empty(deps.as_ref(), &config.distribution_model)?,
+        ));
+        attributes.push(attr("distribution_model", distribution_model.as_str()));
+        config.distribution_model = deps.api.addr_canonicalize(distribution_model.as_str())?;
     }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(attributes))
+}
+
+/// Ownership transfer callback; the new owner has claimed the proposal
+fn store_owner(deps: DepsMut, owner: Addr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner_addr = deps.api.addr_canonicalize(owner.as_str())?;
+    store_config(deps.storage, &config)
+}
+
+/// A cw20 primary market is closed until it receives its initial deposit;
+/// added markets receive it with `AddMarket`
+pub(crate) fn assert_market_open(
+    storage: &dyn Storage,
+    market: &Market,
+) -> Result<(), ContractError> {
+    if market.primary && is_initial_deposit_pending(storage)? {
+        return Err(ContractError::MarketNotOpen {
+            denom: market.stable_denom.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Open a cw20 primary market with its initial deposit; only the owner can
+pub fn receive_initial_deposit(
+    deps: DepsMut,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !is_initial_deposit_pending(deps.storage)? {
+        return Err(ContractError::MarketAlreadyOpen {
+            denom: config.stable_denom,
+        });
+    }
+
+    if amount != Uint128::from(INITIAL_DEPOSIT_AMOUNT) {
+        return Err(ContractError::InitialFundsNotDeposited {
+            denom: config.stable_denom,
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        });
+    }
+
+    remove_pending_initial_deposit(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "initial_deposit"),
+        attr("stable_denom", config.stable_denom),
+        attr("amount", amount),
+    ]))
+}
+
+fn assert_max_borrow_factor_range(max_borrow_factor: Decimal256) -> Result<(), ContractError> {
+    if max_borrow_factor > Decimal256::one() {
+        return Err(ContractError::InvalidMaxBorrowFactor { max_borrow_factor });
+    }
+
+    Ok(())
 }
 
 pub fn receive_cw20(
@@ -194,40 +737,306 @@ pub fn receive_cw20(
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
     let contract_addr = info.sender;
-    match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::RedeemStable {}) => {
-            // only asset contract can execute this message
-            let config: Config = read_config(deps.storage)?;
-            if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
-                return Err(ContractError::Unauthorized {});
-            }
+    let hook_msg: Cw20HookMsg =
+        from_binary(&cw20_msg.msg).map_err(|_| ContractError::InvalidHookMsg {})?;
 
-            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
-            redeem_stable(deps, env, cw20_sender_addr, cw20_msg.amount)
+    let config: Config = read_config(deps.storage)?;
+    let token_addr = deps.api.addr_canonicalize(contract_addr.as_str())?;
+    let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+    match hook_msg {
+        // deposits are sent by the stable token of a market
+        Cw20HookMsg::DepositStable {
+            recipient,
+            min_mint_amount,
+        } => {
+            let stable_asset = AssetInfo::Cw20 {
+                contract_addr: contract_addr.to_string(),
+            };
+            let recipient = optional_addr_validate(deps.api, recipient)?;
+            deposit_stable(
+                deps,
+                env,
+                cw20_sender_addr,
+                stable_asset.to_string(),
+                Uint256::from(cw20_msg.amount),
+                recipient,
+                min_mint_amount,
+            )
+        }
+        // the stable token of a cw20 primary market opens it
+        Cw20HookMsg::InitialDeposit {} => {
+            assert_primary_stable_token(&config, &contract_addr)?;
+            receive_initial_deposit(deps, cw20_sender_addr, cw20_msg.amount)
+        }
+        // repays are sent by the stable token of a market
+        Cw20HookMsg::RepayStable {} => {
+            let stable_asset = AssetInfo::Cw20 {
+                contract_addr: contract_addr.to_string(),
+            };
+            let market = read_denom_market(deps.storage, &config, &stable_asset.to_string())?
+                .ok_or(ContractError::Unauthorized {})?;
+            repay_stable_amount(
+                deps,
+                env,
+                market,
+                cw20_sender_addr,
+                Uint256::from(cw20_msg.amount),
+            )
+        }
+        // redeems are sent by the aterra token of a market
+        Cw20HookMsg::RedeemStable {
+            recipient,
+            min_receive,
+            allow_partial,
+        } => {
+            let market = load_aterra_market(deps.storage, &config, &token_addr)?;
+            let recipient = optional_addr_validate(deps.api, recipient)?;
+            redeem_stable(
+                deps,
+                env,
+                market,
+                cw20_sender_addr,
+                cw20_msg.amount,
+                recipient,
+                min_receive,
+                allow_partial.unwrap_or(false),
+            )
+        }
+        Cw20HookMsg::RedeemExactStable { amount, recipient } => {
+            let market = load_aterra_market(deps.storage, &config, &token_addr)?;
+            let recipient = optional_addr_validate(deps.api, recipient)?;
+            redeem_exact_stable(
+                deps,
+                env,
+                market,
+                cw20_sender_addr,
+                cw20_msg.amount,
+                amount,
+                recipient,
+            )
+        }
+        Cw20HookMsg::QueueRedeem { recipient } => {
+            let market = load_aterra_market(deps.storage, &config, &token_addr)?;
+            let recipient = optional_addr_validate(deps.api, recipient)?;
+            queue_redeem(deps, &market, cw20_sender_addr, cw20_msg.amount, recipient)
         }
-        _ => Err(ContractError::MissingRedeemStableHook {}),
     }
 }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
+/// Market of the aterra token which sent a redeem hook
+fn load_aterra_market(
+    storage: &dyn Storage,
+    config: &Config,
+    token_addr: &CanonicalAddr,
+) -> Result<Market, ContractError> {
+    if *token_addr == config.aterra_contract {
+        return Ok(config.primary_market());
+    }
+
+    let market =
+        read_market_by_aterra(storage, token_addr)?.ok_or(ContractError::Unauthorized {})?;
+    Ok(Market::from(market))
+}
+
+fn assert_primary_stable_token(config: &Config, token_addr: &Addr) -> Result<(), ContractError> {
+    let token_asset = AssetInfo::Cw20 {
+        contract_addr: token_addr.to_string(),
+    };
+    if config.stable_asset() != token_asset {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Balance { address } => {
-            let address = deps.api.canonical_address(&address)?;
-            let balance = balance_get(&deps.storage, &address);
-            let out = to_binary(&BalanceResponse { balance })?;
-            Ok(out)
-        }
-        QueryMsg::Config {} => {
-            let config = config_get(&deps.storage)?;
-            let out = to_binary(&ConfigResponse {
-                name: config.name,
-                symbol: config.symbol,
-                owner: deps.api.human_address(&config.owner)?,
-            })?;
-            Ok(out)
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::State {
+            block_height,
+            denom,
+        } => to_binary(&query_state(deps, block_height, denom)?),
+        QueryMsg::EpochState {
+            block_height,
+            distributed_interest,
+        } => to_binary(&query_epoch_state(
+            deps,
+            block_height,
+            distributed_interest,
+        )?),
+        QueryMsg::BorrowerInfo {
+            borrower,
+            block_height,
+            denom,
+        } => to_binary(&query_borrower_info(
+            deps,
+            deps.api.addr_validate(&borrower)?,
+            block_height,
+            denom,
+        )?),
+        QueryMsg::BorrowerInfos {
+            start_after,
+            limit,
+            denom,
+        } => to_binary(&query_borrower_infos(
+            deps,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+            denom,
+        )?),
+        QueryMsg::WithdrawalRequest { id, denom } => {
+            to_binary(&query_withdrawal_request(deps, id, denom)?)
+        }
+        QueryMsg::WithdrawalRequests {
+            start_after,
+            limit,
+            denom,
+        } => to_binary(&query_withdrawal_requests(deps, start_after, limit, denom)?),
+        QueryMsg::Market { denom } => to_binary(&query_market(deps, denom)?),
+        QueryMsg::Markets { start_after, limit } => {
+            to_binary(&query_markets(deps, start_after, limit)?)
+        }
+        QueryMsg::SimulateDeposit {
+            amount,
+            block_height,
+            denom,
+        } => to_binary(&query_simulate_deposit(deps, amount, block_height, denom)?),
+        QueryMsg::SimulateRedeem {
+            aterra_amount,
+            block_height,
+            denom,
+        } => to_binary(&query_simulate_redeem(
+            deps,
+            aterra_amount,
+            block_height,
+            denom,
+        )?),
+        QueryMsg::ExchangeRateHistory { start_after, limit } => {
+            to_binary(&query_exchange_rate_history(deps, start_after, limit)?)
         }
     }
 }
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner_addr: deps.api.addr_humanize(&config.owner_addr)?.to_string(),
+        aterra_contract: humanize_or_empty(deps, &config.aterra_contract)?,
+        interest_model: humanize_or_empty(deps, &config.interest_model)?,
+        distribution_model: humanize_or_empty(deps, &config.distribution_model)?,
+        overseer_contract: humanize_or_empty(deps, &config.overseer_contract)?,
+        collector_contract: humanize_or_empty(deps, &config.collector_contract)?,
+        distributor_contract: humanize_or_empty(deps, &config.distributor_contract)?,
+        stable_denom: config.stable_denom,
+        max_borrow_factor: config.max_borrow_factor,
+    })
+}
+
+/// Without a block height the stored state is returned as is; otherwise
+/// interest and reward are accrued up to the given height, without being stored
+pub fn query_state(
+    deps: Deps,
+    block_height: Option<u64>,
+    denom: Option<String>,
+) -> StdResult<StateResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let mut state: State = read_market_state(deps.storage, &market)?;
+
+    if let Some(block_height) = block_height {
+        if block_height < state.last_interest_updated {
+            return Err(StdError::generic_err(
+                "block_height must bigger than last_interest_updated",
+            ));
+        }
+
+        if block_height < state.last_reward_updated {
+            return Err(StdError::generic_err(
+                "block_height must bigger than last_reward_updated",
+            ));
+        }
+
+        compute_interest(deps, &config, &market, &mut state, block_height, None)?;
+        compute_reward(&mut state, block_height);
+    }
+
+    Ok(StateResponse {
+        total_liabilities: state.total_liabilities,
+        total_reserves: state.total_reserves,
+        last_interest_updated: state.last_interest_updated,
+        last_reward_updated: state.last_reward_updated,
+        global_interest_index: state.global_interest_index,
+        global_reward_index: state.global_reward_index,
+        anc_emission_rate: state.anc_emission_rate,
+        prev_aterra_supply: state.prev_aterra_supply,
+        prev_exchange_rate: state.prev_exchange_rate,
+    })
+}
+
+pub fn query_epoch_state(
+    deps: Deps,
+    block_height: Option<u64>,
+    distributed_interest: Option<Uint256>,
+) -> StdResult<EpochStateResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let distributed_interest = distributed_interest.unwrap_or_else(Uint256::zero);
+    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
+    let balance = config
+        .stable_asset()
+        .query_balance(deps, deps.api.addr_humanize(&config.contract_addr)?)?
+        - distributed_interest;
+
+    if let Some(block_height) = block_height {
+        if block_height < state.last_interest_updated {
+            return Err(StdError::generic_err(
+                "block_height must bigger than last_interest_updated",
+            ));
+        }
+
+        let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+            deps,
+            deps.api.addr_humanize(&config.interest_model)?,
+            balance,
+            state.total_liabilities,
+            state.total_reserves,
+        )?;
+
+        let target_deposit_rate: Decimal256 =
+            query_target_deposit_rate(deps, deps.api.addr_humanize(&config.overseer_contract)?)?;
+
+        // Compute interest rate to return latest epoch state
+        compute_interest_raw(
+            &mut state,
+            block_height,
+            balance,
+            aterra_supply,
+            borrow_rate_res.rate,
+            target_deposit_rate,
+        );
+    }
+
+    let exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+
+    Ok(EpochStateResponse {
+        exchange_rate,
+        aterra_supply,
+    })
+}
+
+/// Contract addresses are registered after instantiation,
+/// so unregistered ones are returned as an empty string
+fn humanize_or_empty(deps: Deps, addr: &CanonicalAddr) -> StdResult<String> {
+    if addr.is_empty() {
+        return Ok("".to_string());
+    }
+
+    Ok(deps.api.addr_humanize(addr)?.to_string())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    Ok(Response::default())
+}
@@ -0,0 +1,101 @@
+use cosmwasm_std::Api;
+
+use crate::error::ContractError;
+
+use moneymarket::asset::CW20_DENOM_PREFIX;
+
+/// Prefix of the native denoms transferred over IBC
+pub const IBC_DENOM_PREFIX: &str = "ibc/";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DenomKind {
+    /// Native bank denom, e.g. `uusd`
+    Native,
+    /// IBC voucher, `ibc/{sha256 hash of the trace in hex}`
+    Ibc,
+    /// Token contract, `cw20:{contract address}`
+    Cw20,
+}
+
+/// Check the stable identifier is a well formed native,
+/// IBC or cw20 denom
+pub fn validate_stable_denom(api: &dyn Api, denom: &str) -> Result<DenomKind, ContractError> {
+    if let Some(contract_addr) = denom.strip_prefix(CW20_DENOM_PREFIX) {
+        api.addr_validate(contract_addr)
+            .map_err(|_| invalid_denom(denom))?;
+        return Ok(DenomKind::Cw20);
+    }
+
+    if let Some(hash) = denom.strip_prefix(IBC_DENOM_PREFIX) {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid_denom(denom));
+        }
+        return Ok(DenomKind::Ibc);
+    }
+
+    // same rule as the cosmos sdk bank module
+    let mut chars = denom.chars();
+    let starts_with_letter = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic());
+    let valid_chars = chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+    if !starts_with_letter || !valid_chars || denom.len() < 3 || denom.len() > 128 {
+        return Err(invalid_denom(denom));
+    }
+
+    Ok(DenomKind::Native)
+}
+
+/// Name and symbol of the aterra token of a stable denom; they are derived
+/// for the native micro denoms (`uusd` is `Anchor Terra USD`, `aUST`), other
+/// denoms need the explicit values
+pub fn aterra_metadata(
+    api: &dyn Api,
+    stable_denom: &str,
+    name: Option<String>,
+    symbol: Option<String>,
+) -> Result<(String, String), ContractError> {
+    let kind = validate_stable_denom(api, stable_denom)?;
+    let unit = match kind {
+        DenomKind::Native => stable_denom
+            .strip_prefix('u')
+            .filter(|unit| unit.len() >= 2 && unit.chars().all(|c| c.is_ascii_alphabetic())),
+        _ => None,
+    };
+
+    let name = match (name, unit) {
+        (Some(name), _) => name,
+        (None, Some(unit)) => format!("Anchor Terra {}", unit.to_uppercase()),
+        (None, None) => {
+            return Err(ContractError::MissingAterraMetadata {
+                denom: stable_denom.to_string(),
+            })
+        }
+    };
+    let symbol = match (symbol, unit) {
+        (Some(symbol), _) => symbol,
+        (None, Some(unit)) => format!("a{}T", unit[..unit.len() - 1].to_uppercase()),
+        (None, None) => {
+            return Err(ContractError::MissingAterraMetadata {
+                denom: stable_denom.to_string(),
+            })
+        }
+    };
+
+    // same bounds as the cw20 token contract, to fail before instantiating it
+    if name.len() < 3 || name.len() > 50 {
+        return Err(ContractError::InvalidAterraName { name });
+    }
+    if symbol.len() < 3
+        || symbol.len() > 12
+        || !symbol.chars().all(|c| c.is_ascii_alphabetic() || c == '-')
+    {
+        return Err(ContractError::InvalidAterraSymbol { symbol });
+    }
+
+    Ok((name, symbol))
+}
+
+fn invalid_denom(denom: &str) -> ContractError {
+    ContractError::InvalidStableDenom {
+        denom: denom.to_string(),
+    }
+}
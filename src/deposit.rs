@@ -1,41 +1,52 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128, WasmMsg,
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, Response, StdError, StdResult, Uint128,
+    WasmMsg,
 };
 
 use crate::borrow::{compute_interest, compute_reward};
+use crate::contract::assert_market_open;
 use crate::error::ContractError;
-use crate::state::{read_config, read_state, store_state, Config, State};
+use crate::markets::{load_market, market_attributes, query_load_market};
+use crate::state::{read_config, read_market_state, store_market_state, Config, Market, State};
+use crate::withdrawal_queue::settle_withdrawal_queue;
 
 use cw20::Cw20ExecuteMsg;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::asset::AssetInfo;
+use moneymarket::market::SimulationResponse;
+use moneymarket::querier::{query_supply, query_tax_rate, query_tax_rate_and_cap};
 
+/// Deposit the stable coins, or cw20 stable tokens, already
+/// received from the depositor
 pub fn deposit_stable(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    depositor: Addr,
+    stable_denom: String,
+    deposit_amount: Uint256,
+    recipient: Option<Addr>,
+    min_mint_amount: Option<Uint256>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
-    // Check base denom deposit
-    let deposit_amount: Uint256 = info
-        .funds
-        .iter()
-        .find(|c| c.denom == config.stable_denom)
-        .map(|c| Uint256::from(c.amount))
-        .unwrap_or_else(Uint256::zero);
+    // Deposits go to the market of their denom
+    let market = load_market(deps.storage, &config, Some(stable_denom))?;
+    assert_market_open(deps.storage, &market)?;
+    let recipient = recipient.unwrap_or_else(|| depositor.clone());
 
     // Cannot deposit zero amount
     if deposit_amount.is_zero() {
-        return Err(ContractError::ZeroDeposit(config.stable_denom));
+        return Err(ContractError::ZeroDeposit {
+            denom: market.stable_denom,
+        });
     }
 
     // Update interest related state
-    let mut state: State = read_state(deps.storage)?;
+    let mut state: State = read_market_state(deps.storage, &market)?;
     compute_interest(
         deps.as_ref(),
         &config,
+        &market,
         &mut state,
         env.block.height,
         Some(deposit_amount),
@@ -43,113 +54,412 @@ pub fn deposit_stable(
     compute_reward(&mut state, env.block.height);
 
     // Load anchor token exchange rate with updated state
-    let exchange_rate =
-        compute_exchange_rate(deps.as_ref(), &config, &state, Some(deposit_amount))?;
+    let exchange_rate = compute_exchange_rate(
+        deps.as_ref(),
+        &config,
+        &market,
+        &state,
+        Some(deposit_amount),
+    )?;
     let mint_amount = deposit_amount / exchange_rate;
 
+    // Protect the depositor from exchange rate moves
+    if let Some(min_mint_amount) = min_mint_amount {
+        if mint_amount < min_mint_amount {
+            return Err(ContractError::MinMintAmountNotMet {
+                min_mint_amount,
+                mint_amount,
+            });
+        }
+    }
+
     state.prev_aterra_supply += mint_amount;
-    store_state(deps.storage, &state)?;
+
+    // Serve the withdrawal queue with the new liquidity
+    let current_balance = market
+        .stable_asset()
+        .query_balance(deps.as_ref(), env.contract.address)?;
+    let available = compute_available_liquidity(&state, current_balance);
+    let (settle_messages, settle_attributes) =
+        settle_withdrawal_queue(deps.branch(), &market, &mut state, exchange_rate, available)?;
+
+    store_market_state(deps.storage, &market, &state)?;
     Ok(Response::new()
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+            contract_addr: deps.api.addr_humanize(&market.aterra_contract)?.to_string(),
             funds: vec![],
             msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: info.sender.to_string(),
+                recipient: recipient.to_string(),
                 amount: mint_amount.into(),
             })?,
         }))
+        .add_messages(settle_messages)
         .add_attributes(vec![
             attr("action", "deposit_stable"),
-            attr("depositor", info.sender),
+            attr("depositor", depositor),
+            attr("recipient", recipient),
             attr("mint_amount", mint_amount),
             attr("deposit_amount", deposit_amount),
-        ]))
+        ])
+        .add_attributes(market_attributes(&market))
+        .add_attributes(settle_attributes))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn redeem_stable(
     deps: DepsMut,
     env: Env,
+    market: Market,
     sender: Addr,
     burn_amount: Uint128,
+    recipient: Option<Addr>,
+    min_receive: Option<Uint256>,
+    allow_partial: bool,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
+    let recipient = recipient.unwrap_or_else(|| sender.clone());
 
     // Update interest related state
-    let mut state: State = read_state(deps.storage)?;
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    let mut state: State = read_market_state(deps.storage, &market)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &market,
+        &mut state,
+        env.block.height,
+        None,
+    )?;
     compute_reward(&mut state, env.block.height);
 
     // Load anchor token exchange rate with updated state
-    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
-    let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
+    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &market, &state, None)?;
+    let requested_amount = Uint256::from(burn_amount);
+    let mut burn_amount = requested_amount;
+    let mut redeem_amount = burn_amount * exchange_rate;
+
+    let current_balance = market
+        .stable_asset()
+        .query_balance(deps.as_ref(), env.contract.address)?;
+
+    // Burn only the aterra covered by the available liquidity
+    if allow_partial {
+        let available = compute_available_liquidity(&state, current_balance);
+        if redeem_amount > available {
+            burn_amount = available / exchange_rate;
+            redeem_amount = burn_amount * exchange_rate;
+        }
+
+        if redeem_amount.is_zero() {
+            return Err(ContractError::NoStableAvailable {
+                denom: market.stable_denom,
+                requested: requested_amount * exchange_rate,
+                available,
+            });
+        }
+    }
+
+    // Assert redeem amount
+    assert_redeem_amount(&market, &state, current_balance, redeem_amount)?;
+
+    // Protect the redeemer from exchange rate and tax moves
+    let asset = market.stable_asset();
+    let receive_amount = asset.deduct_tax(deps.as_ref(), redeem_amount)?;
+    if let Some(min_receive) = min_receive {
+        if receive_amount < min_receive {
+            return Err(ContractError::MinReceiveNotMet {
+                denom: market.stable_denom.clone(),
+                min_receive,
+                receive_amount,
+            });
+        }
+    }
+
+    state.prev_aterra_supply = state.prev_aterra_supply - burn_amount;
+    store_market_state(deps.storage, &market, &state)?;
+
+    let aterra_contract = deps.api.addr_humanize(&market.aterra_contract)?.to_string();
+    let mut messages: Vec<CosmosMsg> = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract.clone(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: burn_amount.into(),
+            })?,
+        }),
+        asset.transfer_msg(&recipient, receive_amount)?,
+    ];
+
+    // Return the unfilled aterra to the redeemer
+    let unfilled_amount = requested_amount - burn_amount;
+    if !unfilled_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: sender.to_string(),
+                amount: unfilled_amount.into(),
+            })?,
+        }));
+    }
+
+    let mut attributes = vec![
+        attr("action", "redeem_stable"),
+        attr("redeemer", sender),
+        attr("recipient", recipient),
+        attr("burn_amount", burn_amount),
+        attr("redeem_amount", redeem_amount),
+    ];
+    if allow_partial {
+        attributes.push(attr("filled_amount", burn_amount));
+        attributes.push(attr("unfilled_amount", unfilled_amount));
+    }
+    attributes.extend(market_attributes(&market));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+/// Redeem the aterra needed for the recipient to receive exactly
+/// `stable_amount` after tax; the rest of the sent aterra is returned
+pub fn redeem_exact_stable(
+    deps: DepsMut,
+    env: Env,
+    market: Market,
+    sender: Addr,
+    aterra_amount: Uint128,
+    stable_amount: Uint256,
+    recipient: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let recipient = recipient.unwrap_or_else(|| sender.clone());
 
-    let current_balance = query_balance(
+    if stable_amount.is_zero() {
+        return Err(ContractError::ZeroRedeem {
+            denom: market.stable_denom,
+        });
+    }
+
+    // Update interest related state
+    let mut state: State = read_market_state(deps.storage, &market)?;
+    compute_interest(
         deps.as_ref(),
-        env.contract.address,
-        config.stable_denom.to_string(),
+        &config,
+        &market,
+        &mut state,
+        env.block.height,
+        None,
     )?;
+    compute_reward(&mut state, env.block.height);
+
+    // Gross up the stable amount with the tax charged on the transfer
+    let asset = market.stable_asset();
+    let redeem_amount = compute_gross_amount(deps.as_ref(), &asset, stable_amount)?;
+
+    // Load anchor token exchange rate with updated state;
+    // the burn amount is rounded up in favor of the market
+    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &market, &state, None)?;
+    let mut burn_amount = redeem_amount / exchange_rate;
+    if burn_amount * exchange_rate < redeem_amount {
+        burn_amount += Uint256::one();
+    }
+
+    let aterra_amount = Uint256::from(aterra_amount);
+    if burn_amount > aterra_amount {
+        return Err(ContractError::InsufficientRedeemAterra {
+            required: burn_amount,
+            provided: aterra_amount,
+        });
+    }
+
+    let current_balance = asset.query_balance(deps.as_ref(), env.contract.address)?;
 
     // Assert redeem amount
-    assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
+    assert_redeem_amount(&market, &state, current_balance, redeem_amount)?;
+
+    state.prev_aterra_supply = state.prev_aterra_supply - burn_amount;
+    store_market_state(deps.storage, &market, &state)?;
+
+    // report the amount actually sent, not the requested one
+    let receive_amount = asset.deduct_tax(deps.as_ref(), redeem_amount)?;
+
+    let aterra_contract = deps.api.addr_humanize(&market.aterra_contract)?.to_string();
+    let mut messages: Vec<CosmosMsg> = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract.clone(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: burn_amount.into(),
+            })?,
+        }),
+        asset.transfer_msg(&recipient, receive_amount)?,
+    ];
+
+    // Return the excess aterra to the redeemer
+    let refund_amount = aterra_amount - burn_amount;
+    if !refund_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: sender.to_string(),
+                amount: refund_amount.into(),
+            })?,
+        }));
+    }
 
-    state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
-    store_state(deps.storage, &state)?;
     Ok(Response::new()
-        .add_messages(vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
-                funds: vec![],
-                msg: to_binary(&Cw20ExecuteMsg::Burn {
-                    amount: burn_amount,
-                })?,
-            }),
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.to_string(),
-                amount: vec![deduct_tax(
-                    deps.as_ref(),
-                    Coin {
-                        denom: config.stable_denom,
-                        amount: redeem_amount.into(),
-                    },
-                )?],
-            }),
-        ])
+        .add_messages(messages)
         .add_attributes(vec![
-            attr("action", "redeem_stable"),
+            attr("action", "redeem_exact_stable"),
+            attr("redeemer", sender),
+            attr("recipient", recipient),
             attr("burn_amount", burn_amount),
+            attr("refund_amount", refund_amount),
             attr("redeem_amount", redeem_amount),
-        ]))
+            attr("receive_amount", receive_amount),
+        ])
+        .add_attributes(market_attributes(&market)))
 }
 
-fn assert_redeem_amount(
+pub fn query_simulate_deposit(
+    deps: Deps,
+    amount: Uint256,
+    block_height: Option<u64>,
+    denom: Option<String>,
+) -> StdResult<SimulationResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let state: State = read_projected_state(deps, &config, &market, block_height)?;
+
+    let exchange_rate = compute_exchange_rate(deps, &config, &market, &state, None)?;
+
+    // The transfer tax is charged to the depositor on top of the amount
+    let tax_amount = match market.stable_asset() {
+        AssetInfo::Native { denom } => {
+            let (tax_rate, tax_cap) = query_tax_rate_and_cap(deps, denom)?;
+            std::cmp::min(amount * tax_rate, tax_cap)
+        }
+        AssetInfo::Cw20 { .. } => Uint256::zero(),
+    };
+
+    Ok(SimulationResponse {
+        exchange_rate,
+        output_amount: amount / exchange_rate,
+        tax_amount,
+        sufficient_liquidity: true,
+    })
+}
+
+pub fn query_simulate_redeem(
+    deps: Deps,
+    aterra_amount: Uint256,
+    block_height: Option<u64>,
+    denom: Option<String>,
+) -> StdResult<SimulationResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let state: State = read_projected_state(deps, &config, &market, block_height)?;
+
+    let exchange_rate = compute_exchange_rate(deps, &config, &market, &state, None)?;
+    let redeem_amount = aterra_amount * exchange_rate;
+    let tax_amount = market.stable_asset().compute_tax(deps, redeem_amount)?;
+
+    let current_balance = market
+        .stable_asset()
+        .query_balance(deps, deps.api.addr_humanize(&config.contract_addr)?)?;
+
+    Ok(SimulationResponse {
+        exchange_rate,
+        output_amount: redeem_amount - tax_amount,
+        tax_amount,
+        sufficient_liquidity: redeem_amount <= compute_available_liquidity(&state, current_balance),
+    })
+}
+
+/// Load the state with the pending interest and reward accrued up to
+/// `block_height`; the projection is not stored
+fn read_projected_state(
+    deps: Deps,
     config: &Config,
+    market: &Market,
+    block_height: Option<u64>,
+) -> StdResult<State> {
+    let mut state: State = read_market_state(deps.storage, market)?;
+
+    if let Some(block_height) = block_height {
+        if block_height < state.last_interest_updated {
+            return Err(StdError::generic_err(
+                "block_height must bigger than last_interest_updated",
+            ));
+        }
+
+        compute_interest(deps, config, market, &mut state, block_height, None)?;
+        compute_reward(&mut state, block_height);
+    }
+
+    Ok(state)
+}
+
+/// Smallest amount which is worth `net_amount` after the transfer tax
+fn compute_gross_amount(deps: Deps, asset: &AssetInfo, net_amount: Uint256) -> StdResult<Uint256> {
+    if !asset.is_native() {
+        return Ok(net_amount);
+    }
+
+    let tax_rate = query_tax_rate(deps)?;
+    let tax_amount = asset.compute_tax(deps, net_amount * (Decimal256::one() + tax_rate))?;
+
+    // compute_tax rounds the net amount down; compensate it
+    let gross_amount = net_amount + tax_amount;
+    if asset.deduct_tax(deps, gross_amount)? < net_amount {
+        return Ok(gross_amount + Uint256::one());
+    }
+
+    Ok(gross_amount)
+}
+
+fn assert_redeem_amount(
+    market: &Market,
     state: &State,
     current_balance: Uint256,
     redeem_amount: Uint256,
 ) -> Result<(), ContractError> {
-    let current_balance = Decimal256::from_uint256(current_balance);
-    let redeem_amount = Decimal256::from_uint256(redeem_amount);
-    if redeem_amount + state.total_reserves > current_balance {
-        return Err(ContractError::NoStableAvailable(
-            config.stable_denom.clone(),
-        ));
+    if Decimal256::from_uint256(redeem_amount) + state.total_reserves
+        > Decimal256::from_uint256(current_balance)
+    {
+        return Err(ContractError::NoStableAvailable {
+            denom: market.stable_denom.clone(),
+            requested: redeem_amount,
+            available: compute_available_liquidity(state, current_balance),
+        });
     }
 
     Ok(())
 }
 
+/// Stable balance which can be paid out; the reserves are not redeemable
+pub(crate) fn compute_available_liquidity(state: &State, current_balance: Uint256) -> Uint256 {
+    let current_balance = Decimal256::from_uint256(current_balance);
+    if current_balance > state.total_reserves {
+        (current_balance - state.total_reserves) * Uint256::one()
+    } else {
+        Uint256::zero()
+    }
+}
+
 pub(crate) fn compute_exchange_rate(
     deps: Deps,
     config: &Config,
+    market: &Market,
     state: &State,
     deposit_amount: Option<Uint256>,
 ) -> StdResult<Decimal256> {
-    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
-    let balance = query_balance(
-        deps,
-        deps.api.addr_humanize(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
+    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&market.aterra_contract)?)?;
+    let balance = market
+        .stable_asset()
+        .query_balance(deps, deps.api.addr_humanize(&config.contract_addr)?)?
+        - deposit_amount.unwrap_or_else(Uint256::zero);
 
     Ok(compute_exchange_rate_raw(state, aterra_supply, balance))
 }
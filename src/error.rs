@@ -0,0 +1,148 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{StdError, Uint128};
+use moneymarket::common::{FundsError, OwnershipError};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Funds(#[from] FundsError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid reply ID {0}")]
+    InvalidReplyId(u64),
+
+    #[error("aterra contract is already registered")]
+    AterraAlreadyRegistered {},
+
+    #[error("Must deposit initial funds {amount}{denom}")]
+    InitialFundsNotDeposited { denom: String, amount: Uint128 },
+
+    // denom
+    #[error("Invalid stable denom {denom}")]
+    InvalidStableDenom { denom: String },
+
+    #[error("aterra name and symbol must be provided for {denom}")]
+    MissingAterraMetadata { denom: String },
+
+    #[error("Invalid aterra name \"{name}\"; must be 3 to 50 characters")]
+    InvalidAterraName { name: String },
+
+    #[error("Invalid aterra symbol \"{symbol}\"; must be 3 to 12 letters or dashes")]
+    InvalidAterraSymbol { symbol: String },
+
+    // deposit & redeem
+    #[error("Deposit amount must be greater than 0 {denom}")]
+    ZeroDeposit { denom: String },
+
+    #[error("Not enough {denom} available; requested {requested}, available {available}")]
+    NoStableAvailable {
+        denom: String,
+        requested: Uint256,
+        available: Uint256,
+    },
+
+    #[error("Mint amount {mint_amount} is lower than min mint amount {min_mint_amount}")]
+    MinMintAmountNotMet {
+        min_mint_amount: Uint256,
+        mint_amount: Uint256,
+    },
+
+    #[error(
+        "Receive amount {receive_amount}{denom} is lower than min receive {min_receive}{denom}"
+    )]
+    MinReceiveNotMet {
+        denom: String,
+        min_receive: Uint256,
+        receive_amount: Uint256,
+    },
+
+    #[error("Redeem amount must be greater than 0 {denom}")]
+    ZeroRedeem { denom: String },
+
+    #[error("Not enough aterra sent; required {required}, provided {provided}")]
+    InsufficientRedeemAterra {
+        required: Uint256,
+        provided: Uint256,
+    },
+
+    #[error("Queued redeem amount must be greater than 0")]
+    ZeroQueueRedeem {},
+
+    #[error("Withdrawal request {id} not found")]
+    WithdrawalRequestNotFound { id: u64 },
+
+    #[error("Invalid request: unknown cw20 hook message")]
+    InvalidHookMsg {},
+
+    // borrow & repay
+    #[error("Borrow amount must be greater than 0 {denom}")]
+    ZeroBorrow { denom: String },
+
+    #[error(
+        "Borrow amount too high; loan amount {loan_amount}{denom} exceeds borrow limit {borrow_limit}{denom}"
+    )]
+    BorrowExceedsLimit {
+        denom: String,
+        loan_amount: Uint256,
+        borrow_limit: Uint256,
+    },
+
+    #[error(
+        "Borrow amount too high; total liabilities {total_liabilities}{denom} exceed max borrow amount {max_borrow_amount}{denom}"
+    )]
+    MaxBorrowFactorReached {
+        denom: String,
+        total_liabilities: Uint256,
+        max_borrow_amount: Uint256,
+    },
+
+    #[error("Repay amount must be greater than 0 {denom}")]
+    ZeroRepay { denom: String },
+
+    #[error(
+        "Invalid liquidation repay; current balance {current_balance}{denom} is lower than previous balance {prev_balance}{denom}"
+    )]
+    InvalidLiquidationRepay {
+        denom: String,
+        prev_balance: Uint256,
+        current_balance: Uint256,
+    },
+
+    // config
+    #[error("Contracts are already registered")]
+    ContractsAlreadyRegistered {},
+
+    #[error("Contracts must be registered first")]
+    ContractsNotRegistered {},
+
+    #[error("Max borrow factor must be lower than or equal to 1; got {max_borrow_factor}")]
+    InvalidMaxBorrowFactor { max_borrow_factor: Decimal256 },
+
+    // markets
+    #[error("Market for {denom} already exists")]
+    MarketAlreadyExists { denom: String },
+
+    #[error("Market for {denom} not found")]
+    MarketNotFound { denom: String },
+
+    #[error("Market for {denom} still has deposits")]
+    MarketNotEmpty { denom: String },
+
+    #[error("The primary market {denom} cannot be removed")]
+    PrimaryMarketNotRemovable { denom: String },
+
+    #[error("Market for {denom} is waiting for its initial deposit")]
+    MarketNotOpen { denom: String },
+
+    #[error("Market for {denom} already received its initial deposit")]
+    MarketAlreadyOpen { denom: String },
+}
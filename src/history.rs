@@ -0,0 +1,62 @@
+use cosmwasm_std::{Deps, Env, StdResult, Storage};
+
+use crate::state::{
+    read_exchange_rate_history, read_exchange_rate_snapshots, remove_exchange_rate_snapshot,
+    store_exchange_rate_history, store_exchange_rate_snapshot, ExchangeRateHistory,
+    ExchangeRateSnapshot, State,
+};
+
+use moneymarket::market::{ExchangeRateHistoryResponse, ExchangeRateSnapshotResponse};
+
+/// Number of snapshots kept; the oldest one is dropped
+/// when a snapshot is taken on a full history
+pub const MAX_EXCHANGE_RATE_SNAPSHOTS: u64 = 1000;
+
+/// Append the exchange rate and supply of the epoch,
+/// `prev_exchange_rate` and `prev_aterra_supply`, to the history
+pub(crate) fn record_exchange_rate_snapshot(
+    storage: &mut dyn Storage,
+    env: &Env,
+    state: &State,
+) -> StdResult<()> {
+    let mut history: ExchangeRateHistory = read_exchange_rate_history(storage)?;
+    let id = history.next_id;
+    history.next_id += 1;
+
+    store_exchange_rate_snapshot(
+        storage,
+        id,
+        &ExchangeRateSnapshot {
+            height: env.block.height,
+            time: env.block.time.seconds(),
+            exchange_rate: state.prev_exchange_rate,
+            aterra_supply: state.prev_aterra_supply,
+            total_liabilities: state.total_liabilities,
+        },
+    )?;
+    if id >= MAX_EXCHANGE_RATE_SNAPSHOTS {
+        remove_exchange_rate_snapshot(storage, id - MAX_EXCHANGE_RATE_SNAPSHOTS);
+    }
+
+    store_exchange_rate_history(storage, &history)
+}
+
+pub fn query_exchange_rate_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ExchangeRateHistoryResponse> {
+    let snapshots = read_exchange_rate_snapshots(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(id, snapshot)| ExchangeRateSnapshotResponse {
+            id,
+            height: snapshot.height,
+            time: snapshot.time,
+            exchange_rate: snapshot.exchange_rate,
+            aterra_supply: snapshot.aterra_supply,
+            total_liabilities: snapshot.total_liabilities,
+        })
+        .collect();
+
+    Ok(ExchangeRateHistoryResponse { snapshots })
+}
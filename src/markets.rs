@@ -0,0 +1,310 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, Attribute, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+
+use crate::contract::{INITIAL_DEPOSIT_AMOUNT, INSTANTIATE_MARKET_ATERRA_REPLY_ID};
+use crate::denom::aterra_metadata;
+use crate::deposit::compute_exchange_rate_raw;
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_denom_market, read_market, read_market_state, read_markets,
+    read_withdrawal_queue, remove_market_info, store_market, store_market_aterra,
+    store_market_state, store_pending_market, take_pending_market, Config, Market, MarketInfo,
+    State, WithdrawalQueue,
+};
+
+use cw20::{Cw20Coin, Cw20ExecuteMsg, MinterResponse};
+use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
+use moneymarket::asset::AssetInfo;
+use moneymarket::common::{assert_allowed_denoms, assert_no_funds};
+use moneymarket::market::{MarketResponse, MarketsResponse};
+use moneymarket::querier::{query_supply, query_token_balance};
+
+/// Instantiate the aterra token of a market; the initial deposit
+/// is minted to the market itself
+pub(crate) fn instantiate_aterra_msg(
+    env: &Env,
+    aterra_code_id: u64,
+    name: String,
+    symbol: String,
+    reply_id: u64,
+) -> StdResult<SubMsg> {
+    Ok(SubMsg::reply_on_success(
+        CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin: None,
+            code_id: aterra_code_id,
+            funds: vec![],
+            label: "".to_string(),
+            msg: to_binary(&TokenInstantiateMsg {
+                name,
+                symbol,
+                decimals: 6u8,
+                initial_balances: vec![Cw20Coin {
+                    address: env.contract.address.to_string(),
+                    amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                }],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            })?,
+        }),
+        reply_id,
+    ))
+}
+
+/// Market of the given denom; the primary market by default
+pub(crate) fn load_market(
+    storage: &dyn Storage,
+    config: &Config,
+    denom: Option<String>,
+) -> Result<Market, ContractError> {
+    match denom {
+        None => Ok(config.primary_market()),
+        Some(denom) => read_denom_market(storage, config, &denom)?
+            .ok_or(ContractError::MarketNotFound { denom }),
+    }
+}
+
+/// Same as `load_market`, for the queries
+pub(crate) fn query_load_market(
+    storage: &dyn Storage,
+    config: &Config,
+    denom: Option<String>,
+) -> StdResult<Market> {
+    match denom {
+        None => Ok(config.primary_market()),
+        Some(denom) => {
+            read_denom_market(storage, config, &denom)?.ok_or_else(|| StdError::not_found("Market"))
+        }
+    }
+}
+
+/// The events of the primary market are unchanged since added markets
+/// were introduced; those of an added market name its denom
+pub(crate) fn market_attributes(market: &Market) -> Vec<Attribute> {
+    if market.primary {
+        return vec![];
+    }
+
+    vec![attr("stable_denom", market.stable_denom.clone())]
+}
+
+pub fn add_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stable_denom: String,
+    aterra_code_id: u64,
+    aterra_name: Option<String>,
+    aterra_symbol: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (aterra_name, aterra_symbol) =
+        aterra_metadata(deps.api, &stable_denom, aterra_name, aterra_symbol)?;
+
+    if stable_denom == config.stable_denom || read_market(deps.storage, &stable_denom)?.is_some() {
+        return Err(ContractError::MarketAlreadyExists {
+            denom: stable_denom,
+        });
+    }
+
+    // The initial deposit of a cw20 market is pulled from the owner,
+    // who has to approve the allowance beforehand
+    let stable_asset = AssetInfo::from_denom(&stable_denom);
+    let mut messages: Vec<CosmosMsg> = vec![];
+    match &stable_asset {
+        AssetInfo::Native { .. } => {
+            assert_allowed_denoms(&info.funds, &[&stable_denom])?;
+            let initial_deposit = info
+                .funds
+                .iter()
+                .find(|c| c.denom == stable_denom)
+                .map(|c| c.amount)
+                .unwrap_or_else(Uint128::zero);
+
+            if initial_deposit != Uint128::from(INITIAL_DEPOSIT_AMOUNT) {
+                return Err(ContractError::InitialFundsNotDeposited {
+                    denom: stable_denom,
+                    amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                });
+            }
+        }
+        AssetInfo::Cw20 { contract_addr } => {
+            assert_no_funds(&info.funds)?;
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: info.sender.to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                })?,
+            }));
+        }
+    }
+
+    let market = MarketInfo {
+        stable_denom: stable_denom.clone(),
+        aterra_contract: CanonicalAddr::from(vec![]),
+    };
+    store_market(deps.storage, &market)?;
+
+    // ANC rewards only go to the borrowers of the primary market
+    store_market_state(
+        deps.storage,
+        &Market::from(market),
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::zero(),
+            prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            prev_exchange_rate: Decimal256::one(),
+        },
+    )?;
+    store_pending_market(deps.storage, &stable_denom)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessage(instantiate_aterra_msg(
+            &env,
+            aterra_code_id,
+            aterra_name,
+            aterra_symbol,
+            INSTANTIATE_MARKET_ATERRA_REPLY_ID,
+        )?)
+        .add_attributes(vec![
+            attr("action", "add_market"),
+            attr("stable_denom", stable_denom),
+        ]))
+}
+
+/// Store the aterra token address of the market being added
+pub fn register_market_aterra(deps: DepsMut, token_addr: Addr) -> Result<Response, ContractError> {
+    let stable_denom = take_pending_market(deps.storage)?;
+    let mut market =
+        read_market(deps.storage, &stable_denom)?.ok_or(ContractError::MarketNotFound {
+            denom: stable_denom.clone(),
+        })?;
+    if !market.aterra_contract.is_empty() {
+        return Err(ContractError::AterraAlreadyRegistered {});
+    }
+
+    market.aterra_contract = deps.api.addr_canonicalize(token_addr.as_str())?;
+    store_market(deps.storage, &market)?;
+    store_market_aterra(deps.storage, &market.aterra_contract, &stable_denom)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("stable_denom", stable_denom),
+        attr("aterra", token_addr),
+    ]))
+}
+
+pub fn remove_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stable_denom: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if stable_denom == config.stable_denom {
+        return Err(ContractError::PrimaryMarketNotRemovable {
+            denom: stable_denom,
+        });
+    }
+
+    let market =
+        read_market(deps.storage, &stable_denom)?.ok_or(ContractError::MarketNotFound {
+            denom: stable_denom.clone(),
+        })?;
+
+    // Only the aterra of the initial deposit may be left,
+    // with no loan or queued withdrawal outstanding
+    let aterra_contract = deps.api.addr_humanize(&market.aterra_contract)?;
+    let aterra_supply = query_supply(deps.as_ref(), aterra_contract.clone())?;
+    let market_aterra =
+        query_token_balance(deps.as_ref(), aterra_contract, env.contract.address.clone())?;
+    let added_market = Market::from(market.clone());
+    let state: State = read_market_state(deps.storage, &added_market)?;
+    let queue: WithdrawalQueue = read_withdrawal_queue(deps.storage, &added_market)?;
+    if aterra_supply > market_aterra
+        || !state.total_liabilities.is_zero()
+        || !queue.total_aterra.is_zero()
+    {
+        return Err(ContractError::MarketNotEmpty {
+            denom: stable_denom,
+        });
+    }
+
+    remove_market_info(deps.storage, &market);
+
+    let stable_asset = market.stable_asset();
+    let balance = stable_asset.query_balance(deps.as_ref(), env.contract.address)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !balance.is_zero() {
+        messages.push(stable_asset.transfer_msg(
+            &info.sender,
+            stable_asset.deduct_tax(deps.as_ref(), balance)?,
+        )?);
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "remove_market"),
+        attr("stable_denom", stable_denom),
+        attr("returned_amount", balance),
+    ]))
+}
+
+pub fn query_market(deps: Deps, denom: String) -> StdResult<MarketResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, Some(denom))?;
+    to_response(deps, &config, market)
+}
+
+/// Markets added next to the primary one, ordered by denom
+pub fn query_markets(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MarketsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let markets = read_markets(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|market| to_response(deps, &config, Market::from(market)))
+        .collect::<StdResult<Vec<MarketResponse>>>()?;
+
+    Ok(MarketsResponse { markets })
+}
+
+fn to_response(deps: Deps, config: &Config, market: Market) -> StdResult<MarketResponse> {
+    let state: State = read_market_state(deps.storage, &market)?;
+    let aterra_contract = deps.api.addr_humanize(&market.aterra_contract)?;
+    let aterra_supply = query_supply(deps, aterra_contract.clone())?;
+    let balance = market
+        .stable_asset()
+        .query_balance(deps, deps.api.addr_humanize(&config.contract_addr)?)?;
+
+    Ok(MarketResponse {
+        stable_denom: market.stable_denom,
+        aterra_contract: aterra_contract.to_string(),
+        exchange_rate: compute_exchange_rate_raw(&state, aterra_supply, balance),
+        aterra_supply,
+        total_liabilities: state.total_liabilities,
+        total_reserves: state.total_reserves,
+    })
+}
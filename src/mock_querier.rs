@@ -0,0 +1,412 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+};
+use cw20::{BalanceResponse, TokenInfoResponse};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use moneymarket::distribution_model::AncEmissionRateResponse;
+use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::oracle::PriceResponse;
+use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse as OverseerConfigResponse};
+
+use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Query borrow rate to interest model contract
+    BorrowRate {
+        market_balance: Uint256,
+        total_liabilities: Decimal256,
+        total_reserves: Decimal256,
+    },
+    /// Query borrow limit to overseer contract
+    BorrowLimit {
+        borrower: String,
+        block_time: Option<u64>,
+    },
+    /// Query overseer config to get target deposit rate
+    Config {},
+    /// Query cw20 Token Info
+    TokenInfo {},
+    /// Query cw20 Token Balance
+    Balance { address: String },
+    /// Query ANC emission rate to distribution model contract
+    AncEmissionRate {
+        deposit_rate: Decimal256,
+        target_deposit_rate: Decimal256,
+        threshold_deposit_rate: Decimal256,
+        current_emission_rate: Decimal256,
+    },
+    /// Query price to oracle contract
+    Price { base: String, quote: String },
+}
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<TerraQueryWrapper>,
+    token_querier: TokenQuerier,
+    tax_querier: TaxQuerier,
+    borrow_rate_querier: BorrowRateQuerier,
+    borrow_limit_querier: BorrowLimitQuerier,
+    target_deposit_rate: Decimal256,
+    anc_emission_rate: Decimal256,
+    oracle_price_querier: OraclePriceQuerier,
+}
+
+#[derive(Clone, Default)]
+pub struct TokenQuerier {
+    // this lets us iterate over all pairs that match the first string
+    balances: HashMap<String, HashMap<String, Uint128>>,
+}
+
+impl TokenQuerier {
+    pub fn new(balances: &[(&String, &[(&String, &Uint128)])]) -> Self {
+        TokenQuerier {
+            balances: balances_to_map(balances),
+        }
+    }
+}
+
+pub(crate) fn balances_to_map(
+    balances: &[(&String, &[(&String, &Uint128)])],
+) -> HashMap<String, HashMap<String, Uint128>> {
+    let mut balances_map: HashMap<String, HashMap<String, Uint128>> = HashMap::new();
+    for (contract_addr, balances) in balances.iter() {
+        let mut contract_balances_map: HashMap<String, Uint128> = HashMap::new();
+        for (addr, balance) in balances.iter() {
+            contract_balances_map.insert(addr.to_string(), **balance);
+        }
+
+        balances_map.insert(contract_addr.to_string(), contract_balances_map);
+    }
+    balances_map
+}
+
+#[derive(Clone, Default)]
+pub struct TaxQuerier {
+    rate: Decimal,
+    // this lets us iterate over all pairs that match the first string
+    caps: HashMap<String, Uint128>,
+}
+
+impl TaxQuerier {
+    pub fn new(rate: Decimal, caps: &[(&String, &Uint128)]) -> Self {
+        TaxQuerier {
+            rate,
+            caps: caps_to_map(caps),
+        }
+    }
+}
+
+pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint128> {
+    let mut owner_map: HashMap<String, Uint128> = HashMap::new();
+    for (denom, cap) in caps.iter() {
+        owner_map.insert(denom.to_string(), **cap);
+    }
+    owner_map
+}
+
+#[derive(Clone, Default)]
+pub struct BorrowRateQuerier {
+    // this lets us iterate over all pairs that match the first string
+    borrower_rate: HashMap<String, Decimal256>,
+}
+
+impl BorrowRateQuerier {
+    pub fn new(borrower_rate: &[(&String, &Decimal256)]) -> Self {
+        BorrowRateQuerier {
+            borrower_rate: borrower_rate_to_map(borrower_rate),
+        }
+    }
+}
+
+pub(crate) fn borrower_rate_to_map(
+    borrower_rate: &[(&String, &Decimal256)],
+) -> HashMap<String, Decimal256> {
+    let mut borrower_rate_map: HashMap<String, Decimal256> = HashMap::new();
+    for (interest_model, borrower_rate) in borrower_rate.iter() {
+        borrower_rate_map.insert((*interest_model).clone(), **borrower_rate);
+    }
+    borrower_rate_map
+}
+
+#[derive(Clone, Default)]
+pub struct BorrowLimitQuerier {
+    // this lets us iterate over all pairs that match the first string
+    borrow_limit: HashMap<String, Uint256>,
+}
+
+impl BorrowLimitQuerier {
+    pub fn new(borrow_limit: &[(&String, &Uint256)]) -> Self {
+        BorrowLimitQuerier {
+            borrow_limit: borrow_limit_to_map(borrow_limit),
+        }
+    }
+}
+
+pub(crate) fn borrow_limit_to_map(
+    borrow_limit: &[(&String, &Uint256)],
+) -> HashMap<String, Uint256> {
+    let mut borrow_limit_map: HashMap<String, Uint256> = HashMap::new();
+    for (borrower, borrow_limit) in borrow_limit.iter() {
+        borrow_limit_map.insert((*borrower).clone(), **borrow_limit);
+    }
+    borrow_limit_map
+}
+
+#[derive(Clone, Default)]
+pub struct OraclePriceQuerier {
+    // (base, quote) to (rate, last_updated)
+    oracle_price: HashMap<(String, String), (Decimal256, u64)>,
+}
+
+impl OraclePriceQuerier {
+    #[allow(clippy::type_complexity)]
+    pub fn new(oracle_price: &[(&(String, String), &(Decimal256, u64))]) -> Self {
+        OraclePriceQuerier {
+            oracle_price: oracle_price_to_map(oracle_price),
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn oracle_price_to_map(
+    oracle_price: &[(&(String, String), &(Decimal256, u64))],
+) -> HashMap<(String, String), (Decimal256, u64)> {
+    let mut oracle_price_map: HashMap<(String, String), (Decimal256, u64)> = HashMap::new();
+    for (base_quote, oracle_price) in oracle_price.iter() {
+        oracle_price_map.insert((*base_quote).clone(), **oracle_price);
+    }
+    oracle_price_map
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely here
+        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+        match &request {
+            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
+                if &TerraRoute::Treasury == route {
+                    match query_data {
+                        TerraQuery::TaxRate {} => {
+                            let res = TaxRateResponse {
+                                rate: self.tax_querier.rate,
+                            };
+                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                        }
+                        TerraQuery::TaxCap { denom } => {
+                            let cap = self
+                                .tax_querier
+                                .caps
+                                .get(denom)
+                                .copied()
+                                .unwrap_or_default();
+                            let res = TaxCapResponse { cap };
+                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    }
+                } else {
+                    panic!("DO NOT ENTER HERE")
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                match from_binary(msg).unwrap() {
+                    QueryMsg::BorrowRate { .. } => {
+                        match self.borrow_rate_querier.borrower_rate.get(contract_addr) {
+                            Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
+                                &BorrowRateResponse { rate: *v },
+                            ))),
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No borrow rate exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                    QueryMsg::BorrowLimit { borrower, .. } => {
+                        match self.borrow_limit_querier.borrow_limit.get(&borrower) {
+                            Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
+                                &BorrowLimitResponse {
+                                    borrower,
+                                    borrow_limit: *v,
+                                },
+                            ))),
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No borrow limit exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                    QueryMsg::Config {} => {
+                        SystemResult::Ok(ContractResult::from(to_binary(&OverseerConfigResponse {
+                            owner_addr: "".to_string(),
+                            oracle_contract: "oracle".to_string(),
+                            market_contract: "".to_string(),
+                            liquidation_contract: "".to_string(),
+                            collector_contract: "".to_string(),
+                            threshold_deposit_rate: Decimal256::zero(),
+                            target_deposit_rate: self.target_deposit_rate,
+                            buffer_distribution_factor: Decimal256::zero(),
+                            anc_purchase_factor: Decimal256::zero(),
+                            stable_denom: "".to_string(),
+                            epoch_period: 0u64,
+                            price_timeframe: 60u64,
+                            price_guard: None,
+                        })))
+                    }
+                    QueryMsg::TokenInfo {} => {
+                        let balances: &HashMap<String, Uint128> =
+                            match self.token_querier.balances.get(contract_addr) {
+                                Some(balances) => balances,
+                                None => {
+                                    return SystemResult::Err(SystemError::InvalidRequest {
+                                        error: format!(
+                                            "No balance info exists for the contract {}",
+                                            contract_addr
+                                        ),
+                                        request: msg.as_slice().into(),
+                                    })
+                                }
+                            };
+
+                        let mut total_supply = Uint128::zero();
+                        for balance in balances {
+                            total_supply += *balance.1;
+                        }
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&TokenInfoResponse {
+                            name: "mAPPL".to_string(),
+                            symbol: "mAPPL".to_string(),
+                            decimals: 6,
+                            total_supply,
+                        })))
+                    }
+                    QueryMsg::Balance { address } => {
+                        let balance = self
+                            .token_querier
+                            .balances
+                            .get(contract_addr)
+                            .and_then(|balances| balances.get(&address))
+                            .copied()
+                            .unwrap_or_default();
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&BalanceResponse {
+                            balance,
+                        })))
+                    }
+                    QueryMsg::AncEmissionRate { .. } => SystemResult::Ok(ContractResult::from(
+                        to_binary(&AncEmissionRateResponse {
+                            emission_rate: self.anc_emission_rate,
+                        }),
+                    )),
+                    QueryMsg::Price { base, quote } => {
+                        match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
+                            Some(v) => {
+                                SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
+                                    rate: v.0,
+                                    last_updated_base: v.1,
+                                    last_updated_quote: v.1,
+                                })))
+                            }
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No oracle price exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                }
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
+        WasmMockQuerier {
+            base,
+            token_querier: TokenQuerier::default(),
+            tax_querier: TaxQuerier::default(),
+            borrow_rate_querier: BorrowRateQuerier::default(),
+            borrow_limit_querier: BorrowLimitQuerier::default(),
+            target_deposit_rate: Decimal256::zero(),
+            anc_emission_rate: Decimal256::zero(),
+            oracle_price_querier: OraclePriceQuerier::default(),
+        }
+    }
+
+    // configure the mint whitelist mock querier
+    pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
+        self.token_querier = TokenQuerier::new(balances);
+    }
+
+    // update the native balance of the given address
+    pub fn update_balance(&mut self, addr: &str, balance: Vec<Coin>) {
+        self.base.update_balance(addr, balance);
+    }
+
+    // configure the tax mock querier
+    pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
+        self.tax_querier = TaxQuerier::new(rate, caps);
+    }
+
+    // configure the interest model borrow rate mock querier
+    pub fn with_borrow_rate(&mut self, borrower_rate: &[(&String, &Decimal256)]) {
+        self.borrow_rate_querier = BorrowRateQuerier::new(borrower_rate);
+    }
+
+    // configure the overseer borrow limit mock querier
+    pub fn with_borrow_limit(&mut self, borrow_limit: &[(&String, &Uint256)]) {
+        self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
+    }
+
+    // configure the overseer target deposit rate
+    pub fn with_target_deposit_rate(&mut self, target_deposit_rate: Decimal256) {
+        self.target_deposit_rate = target_deposit_rate;
+    }
+
+    // configure the oracle price mock querier
+    #[allow(clippy::type_complexity)]
+    pub fn with_oracle_price(&mut self, oracle_price: &[(&(String, String), &(Decimal256, u64))]) {
+        self.oracle_price_querier = OraclePriceQuerier::new(oracle_price);
+    }
+
+    // configure the distribution model anc emission rate
+    pub fn with_anc_emission_rate(&mut self, anc_emission_rate: Decimal256) {
+        self.anc_emission_rate = anc_emission_rate;
+    }
+}
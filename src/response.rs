@@ -0,0 +1,60 @@
+use cosmwasm_std::{StdError, StdResult};
+
+/// `contract_address` field number of `MsgInstantiateContractResponse`
+const CONTRACT_ADDRESS_FIELD: u64 = 1;
+
+const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_LENGTH_DELIMITED: u64 = 2;
+
+/// Decode the `contract_address` of a protobuf encoded
+/// `MsgInstantiateContractResponse`, which is returned as
+/// reply data of a `WasmMsg::Instantiate` sub message
+pub fn parse_instantiate_contract_address(data: &[u8]) -> StdResult<String> {
+    let mut pos: usize = 0;
+    while pos < data.len() {
+        let key = read_varint(data, &mut pos)?;
+        match key & 0x07 {
+            WIRE_TYPE_VARINT => {
+                read_varint(data, &mut pos)?;
+            }
+            WIRE_TYPE_LENGTH_DELIMITED => {
+                let len = read_varint(data, &mut pos)? as usize;
+                let end = pos
+                    .checked_add(len)
+                    .filter(|end| *end <= data.len())
+                    .ok_or_else(|| parse_err("unexpected end of data"))?;
+
+                if key >> 3 == CONTRACT_ADDRESS_FIELD {
+                    return String::from_utf8(data[pos..end].to_vec())
+                        .map_err(|_| parse_err("contract address is not valid utf-8"));
+                }
+
+                pos = end;
+            }
+            _ => return Err(parse_err("unsupported wire type")),
+        }
+    }
+
+    Err(parse_err("missing contract address"))
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> StdResult<u64> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *data
+            .get(*pos)
+            .ok_or_else(|| parse_err("unexpected end of data"))?;
+        *pos += 1;
+
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(parse_err("varint overflow"))
+}
+
+fn parse_err(msg: &str) -> StdError {
+    StdError::parse_err("MsgInstantiateContractResponse", msg)
+}
@@ -1,38 +1,556 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, StdResult, Storage, Uint128};
-use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, Deps, Order, StdResult, Storage};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
+use moneymarket::asset::AssetInfo;
+use moneymarket::market::BorrowerInfoResponse;
+
+// Storage keys are part of the on-chain layout;
+// renaming them orphans the data of deployed contracts on migration
 pub static CONFIG_KEY: &[u8] = b"config";
-pub static BALANCE_KEY: &[u8] = b"balance";
+pub static STATE_KEY: &[u8] = b"state";
+
+pub static PREFIX_LIABILITY: &[u8] = b"liability";
+
+pub static KEY_WITHDRAWAL_QUEUE: &[u8] = b"withdrawal_queue";
+pub static PREFIX_WITHDRAWAL_REQUEST: &[u8] = b"withdrawal_request";
+
+pub static PREFIX_MARKET: &[u8] = b"market";
+pub static PREFIX_MARKET_STATE: &[u8] = b"market_state";
+pub static PREFIX_MARKET_ATERRA: &[u8] = b"market_aterra";
+pub static PREFIX_MARKET_LIABILITY: &[u8] = b"market_liability";
+pub static PREFIX_MARKET_WITHDRAWAL_QUEUE: &[u8] = b"market_withdrawal_queue";
+pub static PREFIX_MARKET_WITHDRAWAL_REQUEST: &[u8] = b"market_withdrawal_request";
+pub static KEY_PENDING_MARKET: &[u8] = b"pending_market";
+pub static KEY_PENDING_INITIAL_DEPOSIT: &[u8] = b"pending_initial_deposit";
+
+pub static KEY_EXCHANGE_RATE_HISTORY: &[u8] = b"exchange_rate_history";
+pub static PREFIX_EXCHANGE_RATE_SNAPSHOT: &[u8] = b"exchange_rate_snapshot";
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub name: String,
-    pub symbol: String,
+    pub contract_addr: CanonicalAddr,
+    pub owner_addr: CanonicalAddr,
+    pub aterra_contract: CanonicalAddr,
+    pub interest_model: CanonicalAddr,
+    pub distribution_model: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub distributor_contract: CanonicalAddr,
+    pub stable_denom: String,
+    pub max_borrow_factor: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub last_interest_updated: u64,
+    pub last_reward_updated: u64,
+    pub global_interest_index: Decimal256,
+    pub global_reward_index: Decimal256,
+    pub anc_emission_rate: Decimal256,
+    pub prev_aterra_supply: Uint256,
+    pub prev_exchange_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerInfo {
+    pub interest_index: Decimal256,
+    pub reward_index: Decimal256,
+    pub loan_amount: Uint256,
+    pub pending_rewards: Decimal256,
+}
+
+/// Bookkeeping of the withdrawal queue; requests are keyed by
+/// an increasing id, so iterating them in order is first-in-first-out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct WithdrawalQueue {
+    pub next_id: u64,
+    pub total_aterra: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalRequest {
     pub owner: CanonicalAddr,
+    pub recipient: CanonicalAddr,
+    /// Escrowed aterra which is not settled yet
+    pub aterra_amount: Uint256,
 }
 
-pub fn config_set<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
-    Singleton::new(storage, CONFIG_KEY).save(config)
+/// Market of a stable denom other than `Config.stable_denom`;
+/// the primary market keeps its token in the config and its
+/// state under `STATE_KEY`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketInfo {
+    pub stable_denom: String,
+    pub aterra_contract: CanonicalAddr,
 }
 
-pub fn config_get<S: Storage>(storage: &S) -> StdResult<Config> {
+/// Market an operation runs on. The primary market keeps its state,
+/// liabilities and withdrawal queue under the original keys; those of
+/// an added market are stored under the `PREFIX_MARKET_*` prefixes,
+/// namespaced by its denom
+#[derive(Clone, Debug, PartialEq)]
+pub struct Market {
+    pub stable_denom: String,
+    pub aterra_contract: CanonicalAddr,
+    pub primary: bool,
+}
+
+/// Bookkeeping of the exchange rate history; snapshots are keyed by
+/// an increasing id and only the most recent ones are kept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ExchangeRateHistory {
+    pub next_id: u64,
+}
+
+/// Aterra exchange rate of the primary market at an epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateSnapshot {
+    pub height: u64,
+    /// Block time in seconds
+    pub time: u64,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub total_liabilities: Decimal256,
+}
+
+impl Config {
+    pub fn stable_asset(&self) -> AssetInfo {
+        AssetInfo::from_denom(&self.stable_denom)
+    }
+}
+
+impl Config {
+    pub fn primary_market(&self) -> Market {
+        Market {
+            stable_denom: self.stable_denom.clone(),
+            aterra_contract: self.aterra_contract.clone(),
+            primary: true,
+        }
+    }
+}
+
+impl MarketInfo {
+    pub fn stable_asset(&self) -> AssetInfo {
+        AssetInfo::from_denom(&self.stable_denom)
+    }
+}
+
+impl From<MarketInfo> for Market {
+    fn from(market: MarketInfo) -> Self {
+        Market {
+            stable_denom: market.stable_denom,
+            aterra_contract: market.aterra_contract,
+            primary: false,
+        }
+    }
+}
+
+impl Market {
+    pub fn stable_asset(&self) -> AssetInfo {
+        AssetInfo::from_denom(&self.stable_denom)
+    }
+
+    fn namespaces<'a>(&'a self, prefix: &'a [u8], market_prefix: &'a [u8]) -> Vec<&'a [u8]> {
+        if self.primary {
+            vec![prefix]
+        } else {
+            vec![market_prefix, self.stable_denom.as_bytes()]
+        }
+    }
+}
+
+pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
+    Singleton::new(storage, CONFIG_KEY).save(data)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, CONFIG_KEY).load()
 }
 
-pub fn balance_set<S: Storage>(
-    storage: &mut S,
-    address: &CanonicalAddr,
-    amount: &Uint128,
+pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
+    Singleton::new(storage, STATE_KEY).save(data)
+}
+
+pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
+    ReadonlySingleton::new(storage, STATE_KEY).load()
+}
+
+pub fn store_borrower_info(
+    storage: &mut dyn Storage,
+    market: &Market,
+    borrower: &CanonicalAddr,
+    liability: &BorrowerInfo,
+) -> StdResult<()> {
+    Bucket::multilevel(
+        storage,
+        &market.namespaces(PREFIX_LIABILITY, PREFIX_MARKET_LIABILITY),
+    )
+    .save(borrower.as_slice(), liability)
+}
+
+pub fn read_borrower_info(
+    storage: &dyn Storage,
+    market: &Market,
+    borrower: &CanonicalAddr,
+) -> BorrowerInfo {
+    match ReadonlyBucket::multilevel(
+        storage,
+        &market.namespaces(PREFIX_LIABILITY, PREFIX_MARKET_LIABILITY),
+    )
+    .load(borrower.as_slice())
+    {
+        Ok(v) => v,
+        _ => BorrowerInfo {
+            interest_index: Decimal256::one(),
+            reward_index: Decimal256::zero(),
+            loan_amount: Uint256::zero(),
+            pending_rewards: Decimal256::zero(),
+        },
+    }
+}
+
+pub fn read_borrower_infos(
+    deps: Deps,
+    market: &Market,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BorrowerInfoResponse>> {
+    let liability_bucket: ReadonlyBucket<BorrowerInfo> = ReadonlyBucket::multilevel(
+        deps.storage,
+        &market.namespaces(PREFIX_LIABILITY, PREFIX_MARKET_LIABILITY),
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    liability_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let borrower = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            Ok(BorrowerInfoResponse {
+                borrower,
+                interest_index: v.interest_index,
+                reward_index: v.reward_index,
+                loan_amount: v.loan_amount,
+                pending_rewards: v.pending_rewards,
+            })
+        })
+        .collect()
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| {
+        let mut v = addr.as_slice().to_vec();
+        v.push(1);
+        v
+    })
+}
+
+// same as calc_range_start, for the keys of sequential ids
+fn calc_id_range_start(start_after: Option<u64>) -> Option<Vec<u8>> {
+    start_after.map(|id| {
+        let mut v = id.to_be_bytes().to_vec();
+        v.push(1);
+        v
+    })
+}
+
+pub fn store_withdrawal_queue(
+    storage: &mut dyn Storage,
+    market: &Market,
+    data: &WithdrawalQueue,
+) -> StdResult<()> {
+    if market.primary {
+        return Singleton::new(storage, KEY_WITHDRAWAL_QUEUE).save(data);
+    }
+
+    bucket(storage, PREFIX_MARKET_WITHDRAWAL_QUEUE).save(market.stable_denom.as_bytes(), data)
+}
+
+pub fn read_withdrawal_queue(storage: &dyn Storage, market: &Market) -> StdResult<WithdrawalQueue> {
+    let queue: Option<WithdrawalQueue> = if market.primary {
+        ReadonlySingleton::new(storage, KEY_WITHDRAWAL_QUEUE).may_load()?
+    } else {
+        bucket_read(storage, PREFIX_MARKET_WITHDRAWAL_QUEUE)
+            .may_load(market.stable_denom.as_bytes())?
+    };
+
+    Ok(queue.unwrap_or_default())
+}
+
+pub fn store_withdrawal_request(
+    storage: &mut dyn Storage,
+    market: &Market,
+    id: u64,
+    request: &WithdrawalRequest,
 ) -> StdResult<()> {
-    Bucket::new(BALANCE_KEY, storage).save(address.as_slice(), amount)
+    Bucket::multilevel(
+        storage,
+        &market.namespaces(PREFIX_WITHDRAWAL_REQUEST, PREFIX_MARKET_WITHDRAWAL_REQUEST),
+    )
+    .save(&id.to_be_bytes(), request)
+}
+
+pub fn remove_withdrawal_request(storage: &mut dyn Storage, market: &Market, id: u64) {
+    Bucket::<WithdrawalRequest>::multilevel(
+        storage,
+        &market.namespaces(PREFIX_WITHDRAWAL_REQUEST, PREFIX_MARKET_WITHDRAWAL_REQUEST),
+    )
+    .remove(&id.to_be_bytes())
+}
+
+pub fn read_withdrawal_request(
+    storage: &dyn Storage,
+    market: &Market,
+    id: u64,
+) -> StdResult<Option<WithdrawalRequest>> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &market.namespaces(PREFIX_WITHDRAWAL_REQUEST, PREFIX_MARKET_WITHDRAWAL_REQUEST),
+    )
+    .may_load(&id.to_be_bytes())
+}
+
+/// Queued requests in settlement order
+pub fn read_withdrawal_requests(
+    storage: &dyn Storage,
+    market: &Market,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, WithdrawalRequest)>> {
+    let request_bucket: ReadonlyBucket<WithdrawalRequest> = ReadonlyBucket::multilevel(
+        storage,
+        &market.namespaces(PREFIX_WITHDRAWAL_REQUEST, PREFIX_MARKET_WITHDRAWAL_REQUEST),
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_id_range_start(start_after);
+
+    request_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((id_from_key(&k), v))
+        })
+        .collect()
 }
 
-pub fn balance_get<S: Storage>(storage: &S, address: &CanonicalAddr) -> Uint128 {
-    match ReadonlyBucket::new(BALANCE_KEY, storage).may_load(address.as_slice()) {
-        Ok(Some(amount)) => amount,
-        _ => Uint128::zero(),
+/// Number of requests and aterra amount queued before the given request
+pub fn read_withdrawal_queue_ahead(
+    storage: &dyn Storage,
+    market: &Market,
+    id: u64,
+) -> StdResult<(u64, Uint256)> {
+    let request_bucket: ReadonlyBucket<WithdrawalRequest> = ReadonlyBucket::multilevel(
+        storage,
+        &market.namespaces(PREFIX_WITHDRAWAL_REQUEST, PREFIX_MARKET_WITHDRAWAL_REQUEST),
+    );
+
+    let mut position = 0u64;
+    let mut aterra_ahead = Uint256::zero();
+    for elem in request_bucket.range(None, Some(&id.to_be_bytes()), Order::Ascending) {
+        let (_, v) = elem?;
+        position += 1;
+        aterra_ahead += v.aterra_amount;
     }
+
+    Ok((position, aterra_ahead))
+}
+
+fn id_from_key(key: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&key[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+pub fn store_market(storage: &mut dyn Storage, market: &MarketInfo) -> StdResult<()> {
+    bucket(storage, PREFIX_MARKET).save(market.stable_denom.as_bytes(), market)
+}
+
+/// Drop an added market with its bookkeeping; its liabilities and
+/// withdrawal requests have to be settled beforehand
+pub fn remove_market_info(storage: &mut dyn Storage, market: &MarketInfo) {
+    bucket::<MarketInfo>(storage, PREFIX_MARKET).remove(market.stable_denom.as_bytes());
+    bucket::<State>(storage, PREFIX_MARKET_STATE).remove(market.stable_denom.as_bytes());
+    bucket::<WithdrawalQueue>(storage, PREFIX_MARKET_WITHDRAWAL_QUEUE)
+        .remove(market.stable_denom.as_bytes());
+    bucket::<String>(storage, PREFIX_MARKET_ATERRA).remove(market.aterra_contract.as_slice());
+}
+
+pub fn read_market(storage: &dyn Storage, stable_denom: &str) -> StdResult<Option<MarketInfo>> {
+    bucket_read(storage, PREFIX_MARKET).may_load(stable_denom.as_bytes())
+}
+
+/// The primary market for its denom, otherwise the added market of the denom
+pub fn read_denom_market(
+    storage: &dyn Storage,
+    config: &Config,
+    stable_denom: &str,
+) -> StdResult<Option<Market>> {
+    if stable_denom == config.stable_denom {
+        return Ok(Some(config.primary_market()));
+    }
+
+    Ok(read_market(storage, stable_denom)?.map(Market::from))
+}
+
+/// Every added market, for the operations which cover all of them;
+/// markets are only added by the owner, so the list stays short
+pub fn read_all_markets(storage: &dyn Storage) -> StdResult<Vec<Market>> {
+    let market_bucket: ReadonlyBucket<MarketInfo> = ReadonlyBucket::new(storage, PREFIX_MARKET);
+
+    market_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| Ok(Market::from(elem?.1)))
+        .collect()
+}
+
+pub fn read_markets(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<MarketInfo>> {
+    let market_bucket: ReadonlyBucket<MarketInfo> = ReadonlyBucket::new(storage, PREFIX_MARKET);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|denom| {
+        let mut v = denom.into_bytes();
+        v.push(1);
+        v
+    });
+
+    market_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
+
+pub fn store_market_state(
+    storage: &mut dyn Storage,
+    market: &Market,
+    data: &State,
+) -> StdResult<()> {
+    if market.primary {
+        return store_state(storage, data);
+    }
+
+    bucket(storage, PREFIX_MARKET_STATE).save(market.stable_denom.as_bytes(), data)
+}
+
+pub fn read_market_state(storage: &dyn Storage, market: &Market) -> StdResult<State> {
+    if market.primary {
+        return read_state(storage);
+    }
+
+    bucket_read(storage, PREFIX_MARKET_STATE).load(market.stable_denom.as_bytes())
+}
+
+/// Index of the markets by their aterra token, to route the redeem hooks
+pub fn store_market_aterra(
+    storage: &mut dyn Storage,
+    aterra_contract: &CanonicalAddr,
+    stable_denom: &str,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_MARKET_ATERRA)
+        .save(aterra_contract.as_slice(), &stable_denom.to_string())
+}
+
+pub fn read_market_by_aterra(
+    storage: &dyn Storage,
+    aterra_contract: &CanonicalAddr,
+) -> StdResult<Option<MarketInfo>> {
+    let stable_denom: Option<String> =
+        bucket_read(storage, PREFIX_MARKET_ATERRA).may_load(aterra_contract.as_slice())?;
+    match stable_denom {
+        Some(stable_denom) => read_market(storage, &stable_denom),
+        None => Ok(None),
+    }
+}
+
+/// Denom of the market whose aterra token is being instantiated
+pub fn store_pending_market(storage: &mut dyn Storage, stable_denom: &str) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_MARKET).save(&stable_denom.to_string())
+}
+
+pub fn take_pending_market(storage: &mut dyn Storage) -> StdResult<String> {
+    let stable_denom: String = ReadonlySingleton::new(storage, KEY_PENDING_MARKET).load()?;
+    Singleton::<String>::new(storage, KEY_PENDING_MARKET).remove();
+    Ok(stable_denom)
+}
+
+/// Set while a cw20 primary market waits for its initial deposit
+pub fn store_pending_initial_deposit(storage: &mut dyn Storage) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_INITIAL_DEPOSIT).save(&true)
+}
+
+pub fn is_initial_deposit_pending(storage: &dyn Storage) -> StdResult<bool> {
+    let pending: Option<bool> =
+        ReadonlySingleton::new(storage, KEY_PENDING_INITIAL_DEPOSIT).may_load()?;
+    Ok(pending.is_some())
+}
+
+pub fn remove_pending_initial_deposit(storage: &mut dyn Storage) {
+    Singleton::<bool>::new(storage, KEY_PENDING_INITIAL_DEPOSIT).remove()
+}
+
+pub fn store_exchange_rate_history(
+    storage: &mut dyn Storage,
+    data: &ExchangeRateHistory,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_EXCHANGE_RATE_HISTORY).save(data)
+}
+
+pub fn read_exchange_rate_history(storage: &dyn Storage) -> StdResult<ExchangeRateHistory> {
+    Ok(ReadonlySingleton::new(storage, KEY_EXCHANGE_RATE_HISTORY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_exchange_rate_snapshot(
+    storage: &mut dyn Storage,
+    id: u64,
+    snapshot: &ExchangeRateSnapshot,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_EXCHANGE_RATE_SNAPSHOT).save(&id.to_be_bytes(), snapshot)
+}
+
+pub fn remove_exchange_rate_snapshot(storage: &mut dyn Storage, id: u64) {
+    bucket::<ExchangeRateSnapshot>(storage, PREFIX_EXCHANGE_RATE_SNAPSHOT).remove(&id.to_be_bytes())
+}
+
+/// Snapshots from the oldest to the most recent
+pub fn read_exchange_rate_snapshots(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, ExchangeRateSnapshot)>> {
+    let snapshot_bucket: ReadonlyBucket<ExchangeRateSnapshot> =
+        ReadonlyBucket::new(storage, PREFIX_EXCHANGE_RATE_SNAPSHOT);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_id_range_start(start_after);
+
+    snapshot_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((id_from_key(&k), v))
+        })
+        .collect()
 }
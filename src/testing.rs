@@ -0,0 +1,4204 @@
+use crate::borrow::FaucetExecuteMsg;
+use crate::contract::{
+    execute, instantiate, query, reply, INITIAL_DEPOSIT_AMOUNT, INSTANTIATE_ATERRA_REPLY_ID,
+    INSTANTIATE_MARKET_ATERRA_REPLY_ID,
+};
+use crate::denom::aterra_metadata;
+use crate::error::ContractError;
+use crate::history::{record_exchange_rate_snapshot, MAX_EXCHANGE_RATE_SNAPSHOTS};
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
+use crate::state::{
+    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
+    store_state, BorrowerInfo, Market,
+};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Api, BankMsg, Binary, CanonicalAddr, Coin, ContractResult,
+    CosmosMsg, Decimal, Env, OwnedDeps, Reply, StdError, Storage, SubMsg, SubMsgExecutionResponse,
+    Uint128, WasmMsg,
+};
+use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
+use moneymarket::common::{read_ownership_proposal, FundsError, OwnershipError, MAX_PROPOSAL_TTL};
+use moneymarket::market::{
+    BorrowerInfoResponse, BorrowerInfosResponse, ConfigResponse, Cw20HookMsg, EpochStateResponse,
+    ExchangeRateHistoryResponse, ExchangeRateSnapshotResponse, ExecuteMsg, InstantiateMsg,
+    MarketResponse, MarketsResponse, QueryMsg, SimulationResponse, StateResponse,
+    WithdrawalRequestResponse, WithdrawalRequestsResponse, SECONDS_PER_YEAR,
+};
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        aterra_name: None,
+        aterra_symbol: None,
+    }
+}
+
+/// Protobuf encoded `MsgInstantiateContractResponse` of the given contract
+fn instantiate_response_data(contract_address: &str) -> Binary {
+    let mut data = vec![0x0a, contract_address.len() as u8];
+    data.extend_from_slice(contract_address.as_bytes());
+    Binary::from(data)
+}
+
+fn aterra_instantiate_reply(data: Option<Binary>) -> Reply {
+    Reply {
+        id: INSTANTIATE_ATERRA_REPLY_ID,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data,
+        }),
+    }
+}
+
+/// Instantiate the market and register the aterra token
+/// and the contracts the market depends on
+fn setup_market(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, msg: InstantiateMsg) {
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        aterra_instantiate_reply(Some(instantiate_response_data("at-uusd"))),
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        register_contracts_msg(),
+    )
+    .unwrap();
+}
+
+fn register_contracts_msg() -> ExecuteMsg {
+    ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    }
+}
+
+/// Env advanced by the given number of blocks
+fn mock_env_after_blocks(blocks: u64) -> Env {
+    let mut env = mock_env();
+    env.block.height += blocks;
+    env
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = instantiate_msg();
+
+    // initial deposit is required
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::InitialFundsNotDeposited { denom, amount }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(amount, Uint128::from(INITIAL_DEPOSIT_AMOUNT));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: None,
+                code_id: 123u64,
+                funds: vec![],
+                label: "".to_string(),
+                msg: to_binary(&TokenInstantiateMsg {
+                    name: "Anchor Terra USD".to_string(),
+                    symbol: "aUST".to_string(),
+                    decimals: 6u8,
+                    initial_balances: vec![Cw20Coin {
+                        address: MOCK_CONTRACT_ADDR.to_string(),
+                        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                    }],
+                    mint: Some(MinterResponse {
+                        minter: MOCK_CONTRACT_ADDR.to_string(),
+                        cap: None,
+                    }),
+                    marketing: None,
+                })
+                .unwrap(),
+            }),
+            1
+        )]
+    );
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&query_res).unwrap();
+    assert_eq!("owner".to_string(), config_res.owner_addr);
+    assert_eq!("".to_string(), config_res.aterra_contract);
+    assert_eq!("uusd".to_string(), config_res.stable_denom);
+    assert_eq!(Decimal256::one(), config_res.max_borrow_factor);
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::State {
+            block_height: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let state: StateResponse = from_binary(&query_res).unwrap();
+    assert_eq!(Decimal256::zero(), state.total_liabilities);
+    assert_eq!(Decimal256::zero(), state.total_reserves);
+    assert_eq!(mock_env().block.height, state.last_interest_updated);
+    assert_eq!(Decimal256::one(), state.global_interest_index);
+    assert_eq!(Decimal256::one(), state.anc_emission_rate);
+    assert_eq!(
+        Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+        state.prev_aterra_supply
+    );
+    assert_eq!(Decimal256::one(), state.prev_exchange_rate);
+}
+
+#[test]
+fn reply_registers_aterra() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    // unknown reply id
+    let mut msg = aterra_instantiate_reply(Some(instantiate_response_data("at-uusd")));
+    msg.id = 3u64;
+    let res = reply(deps.as_mut(), mock_env(), msg);
+    match res {
+        Err(ContractError::InvalidReplyId(id)) => assert_eq!(id, 3u64),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // missing or truncated instantiate response
+    let res = reply(deps.as_mut(), mock_env(), aterra_instantiate_reply(None));
+    match res {
+        Err(ContractError::Std(StdError::ParseErr { .. })) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        aterra_instantiate_reply(Some(Binary::from(vec![0x0a, 0x07, b'a', b't']))),
+    );
+    match res {
+        Err(ContractError::Std(StdError::ParseErr { .. })) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // trailing fields of the response are ignored
+    let mut data = instantiate_response_data("at-uusd").to_vec();
+    data.extend_from_slice(&[0x12, 0x00]);
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        aterra_instantiate_reply(Some(Binary::from(data))),
+    )
+    .unwrap();
+    assert_eq!(res.attributes, vec![attr("aterra", "at-uusd")]);
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&query_res).unwrap();
+    assert_eq!("at-uusd".to_string(), config_res.aterra_contract);
+
+    // the token address can only be registered once
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        aterra_instantiate_reply(Some(instantiate_response_data("at-other"))),
+    );
+    match res {
+        Err(ContractError::AterraAlreadyRegistered {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn deposit_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    // must deposit the denom of a market
+    let msg = ExecuteMsg::DepositStable {
+        recipient: None,
+        min_mint_amount: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "ukrw".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::MarketNotFound { denom }) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deposit_stable"),
+            attr("depositor", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("mint_amount", "1000000"),
+            attr("deposit_amount", "1000000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.prev_aterra_supply,
+        Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128)
+    );
+
+    // deposit on behalf of another address
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::DepositStable {
+            recipient: Some("addr0001".to_string()),
+            min_mint_amount: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deposit_stable"),
+            attr("depositor", "addr0000"),
+            attr("recipient", "addr0001"),
+            attr("mint_amount", "1000000"),
+            attr("deposit_amount", "1000000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+}
+
+#[test]
+fn redeem_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: None,
+            allow_partial: None,
+        })
+        .unwrap(),
+    });
+
+    // only the aterra contract can send the redeem hook
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("at-uusd", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(990099u128),
+                }]
+            }))
+        ]
+    );
+
+    // redeem to another address
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: Some("addr0001".to_string()),
+            min_receive: None,
+            allow_partial: None,
+        })
+        .unwrap(),
+    });
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    let info = mock_info("at-uusd", &[]);
+    let res = execute(deps.as_mut(), mock_env_after_blocks(1), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "redeem_stable"),
+            attr("redeemer", "addr0000"),
+            attr("recipient", "addr0001"),
+            attr("burn_amount", "100000"),
+            attr("redeem_amount", "100000"),
+        ]
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0001".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99009u128),
+            }]
+        }))
+    );
+
+    // cannot redeem more than the market holds
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(3000000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: None,
+            allow_partial: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("at-uusd", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::NoStableAvailable {
+            denom,
+            requested,
+            available,
+        }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(requested, Uint256::from(3000000u64));
+            assert_eq!(available, Uint256::from(2000000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn borrow_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    let mut msg = instantiate_msg();
+    msg.max_borrow_factor = Decimal256::percent(10);
+    setup_market(&mut deps, msg);
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::zero(),
+            to: None,
+            denom: None,
+        },
+    );
+    match res {
+        Err(ContractError::ZeroBorrow { denom }) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // cannot borrow more than the overseer borrow limit
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(1500000u64),
+            to: None,
+            denom: None,
+        },
+    );
+    match res {
+        Err(ContractError::BorrowExceedsLimit {
+            denom,
+            loan_amount,
+            borrow_limit,
+        }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(loan_amount, Uint256::from(1500000u64));
+            assert_eq!(borrow_limit, Uint256::from(1000000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // max borrow amount is 10% of the total deposits
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(500000u64),
+            to: None,
+            denom: None,
+        },
+    );
+    match res {
+        Err(ContractError::MaxBorrowFactorReached {
+            denom,
+            total_liabilities,
+            max_borrow_amount,
+        }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(total_liabilities, Uint256::from(500000u64));
+            assert_eq!(max_borrow_amount, Uint256::from(200000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "borrow_stable"),
+            attr("borrower", "addr0000"),
+            attr("borrow_amount", "100000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100000u128),
+            }]
+        }))]
+    );
+
+    let liability = read_borrower_info(
+        &deps.storage,
+        &read_config(&deps.storage).unwrap().primary_market(),
+        &deps.api.addr_canonicalize("addr0000").unwrap(),
+    );
+    assert_eq!(liability.loan_amount, Uint256::from(100000u64));
+    assert_eq!(liability.interest_index, Decimal256::one());
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(100000u64));
+}
+
+#[test]
+fn repay_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    // keep all the interest for depositors
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::RepayStable {},
+    );
+    match res {
+        Err(ContractError::ZeroRepay { denom }) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // 100 blocks * 0.1% interest accrued; loan is 110000
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(50000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        info,
+        ExecuteMsg::RepayStable {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "50000"),
+        ]
+    );
+    assert!(res.messages.is_empty());
+
+    let liability = read_borrower_info(
+        &deps.storage,
+        &read_config(&deps.storage).unwrap().primary_market(),
+        &deps.api.addr_canonicalize("addr0000").unwrap(),
+    );
+    assert_eq!(liability.loan_amount, Uint256::from(60000u64));
+    assert_eq!(liability.interest_index, Decimal256::from_ratio(11, 10));
+
+    // over repaid amount is returned to the borrower
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        info,
+        ExecuteMsg::RepayStable {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "60000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(40000u128),
+            }]
+        }))]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+}
+
+#[test]
+fn repay_stable_from_liquidation() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::RepayStableFromLiquidation {
+        borrower: "addr0000".to_string(),
+        prev_balance: Uint256::from(1900000u64),
+        denom: None,
+    };
+
+    // only overseer can repay from liquidation
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("overseer", &[]),
+        ExecuteMsg::RepayStableFromLiquidation {
+            borrower: "addr0000".to_string(),
+            prev_balance: Uint256::from(3000000u64),
+            denom: None,
+        },
+    );
+    match res {
+        Err(ContractError::InvalidLiquidationRepay {
+            denom,
+            prev_balance,
+            current_balance,
+        }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(prev_balance, Uint256::from(3000000u64));
+            assert_eq!(current_balance, Uint256::from(2000000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "100000"),
+        ]
+    );
+
+    let liability = read_borrower_info(
+        &deps.storage,
+        &read_config(&deps.storage).unwrap().primary_market(),
+        &deps.api.addr_canonicalize("addr0000").unwrap(),
+    );
+    assert_eq!(liability.loan_amount, Uint256::zero());
+}
+
+#[test]
+fn claim_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    // 100 blocks * 1 ANC distributed to the only borrower
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimRewards {
+            to: Some("addr0002".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_rewards"),
+            attr("borrower", "addr0000"),
+            attr("claim_amount", "100"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "distributor".to_string(),
+            funds: vec![],
+            msg: to_binary(&FaucetExecuteMsg::Spend {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::from(100u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // nothing left to claim
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimRewards { to: None },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn storage_layout() {
+    let mut deps = mock_dependencies(&[]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    // config and state live under fixed keys across migrations;
+    // singleton keys are length prefixed
+    assert!(deps.storage.get(b"\x00\x06config").is_some());
+    assert!(deps.storage.get(b"\x00\x05state").is_some());
+
+    let borrower_raw = deps.api.addr_canonicalize("addr0000").unwrap();
+    let liability = BorrowerInfo {
+        interest_index: Decimal256::one(),
+        reward_index: Decimal256::zero(),
+        loan_amount: Uint256::from(100u64),
+        pending_rewards: Decimal256::zero(),
+    };
+    let market = read_config(&deps.storage).unwrap().primary_market();
+    store_borrower_info(&mut deps.storage, &market, &borrower_raw, &liability).unwrap();
+
+    let mut key = b"\x00\x09liability".to_vec();
+    key.extend_from_slice(borrower_raw.as_slice());
+    assert!(deps.storage.get(&key).is_some());
+}
+
+#[test]
+fn borrower_infos_pagination() {
+    let mut deps = mock_dependencies(&[]);
+    let market = Market {
+        stable_denom: "uusd".to_string(),
+        aterra_contract: CanonicalAddr::from(vec![]),
+        primary: true,
+    };
+
+    let mut borrowers: Vec<String> = (0..35).map(|i| format!("addr{:04}", i)).collect();
+    for (i, borrower) in borrowers.iter().enumerate() {
+        store_borrower_info(
+            &mut deps.storage,
+            &market,
+            &deps.api.addr_canonicalize(borrower).unwrap(),
+            &BorrowerInfo {
+                interest_index: Decimal256::one(),
+                reward_index: Decimal256::zero(),
+                loan_amount: Uint256::from(i as u64),
+                pending_rewards: Decimal256::zero(),
+            },
+        )
+        .unwrap();
+    }
+
+    // results are ordered by canonical address
+    borrowers.sort_by_key(|b| deps.api.addr_canonicalize(b).unwrap().to_vec());
+
+    let page = read_borrower_infos(deps.as_ref(), &market, None, None).unwrap();
+    assert_eq!(page.len(), 10);
+    assert_eq!(page[0].borrower, borrowers[0]);
+
+    // limit is capped
+    let page = read_borrower_infos(deps.as_ref(), &market, None, Some(100)).unwrap();
+    assert_eq!(page.len(), 30);
+
+    let start_after = deps.api.addr_canonicalize(&borrowers[29]).unwrap();
+    let page = read_borrower_infos(deps.as_ref(), &market, Some(start_after), Some(30)).unwrap();
+    assert_eq!(
+        page.iter()
+            .map(|b| b.borrower.clone())
+            .collect::<Vec<String>>(),
+        borrowers[30..].to_vec()
+    );
+}
+
+#[test]
+fn query_projected_states() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    let stored_state = read_state(&deps.storage).unwrap();
+    let future_height = mock_env().block.height + 100;
+
+    // 100 blocks * 0.1% interest, 100 blocks * 1 ANC reward
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::State {
+            block_height: Some(future_height),
+            denom: None,
+        },
+    )
+    .unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(110000u64));
+    assert_eq!(state.global_interest_index, Decimal256::percent(110));
+    assert_eq!(state.global_reward_index, Decimal256::permille(1));
+    assert_eq!(state.last_interest_updated, future_height);
+    assert_eq!(state.last_reward_updated, future_height);
+
+    // the projection is never stored
+    assert_eq!(read_state(&deps.storage).unwrap(), stored_state);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::State {
+            block_height: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.total_liabilities, stored_state.total_liabilities);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::State {
+            block_height: Some(mock_env().block.height - 1),
+            denom: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "block_height must bigger than last_interest_updated")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // exchange_rate = (2000000 + 100000) / 2000000
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochState {
+            block_height: None,
+            distributed_interest: None,
+        },
+    )
+    .unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        epoch_state,
+        EpochStateResponse {
+            exchange_rate: Decimal256::percent(105),
+            aterra_supply: Uint256::from(2000000u64),
+        }
+    );
+
+    // exchange_rate = (2000000 - 10000 + 110000) / 2000000
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochState {
+            block_height: Some(future_height),
+            distributed_interest: Some(Uint256::from(10000u64)),
+        },
+    )
+    .unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        epoch_state,
+        EpochStateResponse {
+            exchange_rate: Decimal256::percent(105),
+            aterra_supply: Uint256::from(2000000u64),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: Some(future_height),
+            denom: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_info,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            interest_index: Decimal256::percent(110),
+            reward_index: Decimal256::permille(1),
+            loan_amount: Uint256::from(110000u64),
+            pending_rewards: Decimal256::from_uint256(100u64),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BorrowerInfos {
+            start_after: None,
+            limit: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let borrower_infos: BorrowerInfosResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_infos.borrower_infos,
+        vec![BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            interest_index: Decimal256::one(),
+            reward_index: Decimal256::zero(),
+            loan_amount: Uint256::from(100000u64),
+            pending_rewards: Decimal256::zero(),
+        }]
+    );
+}
+
+#[test]
+fn execute_epoch_operations() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+    deps.querier
+        .with_anc_emission_rate(Decimal256::from_uint256(5u64));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::permille(1),
+        target_deposit_rate: Decimal256::from_ratio(5u64, 10000u64),
+        threshold_deposit_rate: Decimal256::from_ratio(3u64, 10000u64),
+        distributed_interest: Uint256::zero(),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // deposit_rate = (2110000 / 2000000 - 1) / 100 = 0.00055
+    // excess_deposits = 1000000 * (0.00055 - 0.0005) * 100 = 5000
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("overseer", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "collector".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(5000u128),
+            }]
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "execute_epoch_operations"),
+            attr("total_liabilities", "110000"),
+            attr("prev_aterra_supply", "2000000"),
+            attr("prev_exchange_rate", "1.0525"),
+            attr("total_reserves", "5000"),
+            attr("anc_emission_rate", "5"),
+        ]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_reserves, Decimal256::zero());
+    assert_eq!(state.anc_emission_rate, Decimal256::from_uint256(5u64));
+    assert_eq!(
+        state.prev_exchange_rate,
+        Decimal256::from_ratio(10525u64, 10000u64)
+    );
+    assert_eq!(state.last_interest_updated, mock_env().block.height + 100);
+    assert_eq!(state.last_reward_updated, mock_env().block.height + 100);
+}
+
+#[test]
+fn execute_epoch_operations_with_distributed_interest() {
+    // the overseer has sent 100000 of distributed interest
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1100000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+    deps.querier
+        .with_anc_emission_rate(Decimal256::from_uint256(5u64));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    // the deposit rate excludes the distributed interest:
+    // deposit_rate = (2110000 / 2000000 - 1) / 100 = 0.00055
+    // excess_deposits = 1000000 * (0.00055 - 0.0005) * 100 = 5000
+    // the exchange rate includes it:
+    // prev_exchange_rate = (2100000 + 110000 - 5000) / 2000000 = 1.1025
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("overseer", &[]),
+        ExecuteMsg::ExecuteEpochOperations {
+            deposit_rate: Decimal256::permille(1),
+            target_deposit_rate: Decimal256::from_ratio(5u64, 10000u64),
+            threshold_deposit_rate: Decimal256::from_ratio(3u64, 10000u64),
+            distributed_interest: Uint256::from(100000u64),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "collector".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(5000u128),
+            }]
+        }))]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.prev_exchange_rate,
+        Decimal256::from_ratio(11025u64, 10000u64)
+    );
+
+    // the distributed interest is not mistaken for an excess deposit rate
+    // at the next epoch:
+    // exchange_rate = (2095000 + 121000) / 2000000 = 1.108
+    // deposit_rate = (1.108 / 1.1025 - 1) / 100 < 0.0005
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1095000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(200),
+        mock_info("overseer", &[]),
+        ExecuteMsg::ExecuteEpochOperations {
+            deposit_rate: Decimal256::permille(1),
+            target_deposit_rate: Decimal256::from_ratio(5u64, 10000u64),
+            threshold_deposit_rate: Decimal256::from_ratio(3u64, 10000u64),
+            distributed_interest: Uint256::zero(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_reserves, Decimal256::zero());
+    assert_eq!(
+        state.prev_exchange_rate,
+        Decimal256::from_ratio(1108u64, 1000u64)
+    );
+}
+
+#[test]
+fn register_contracts() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    // the models cannot be set ahead of the registration
+    for (interest_model, distribution_model) in [
+        (Some("interest2".to_string()), None),
+        (None, Some("distribution2".to_string())),
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::UpdateConfig {
+                max_borrow_factor: None,
+                interest_model,
+                distribution_model,
+            },
+        );
+        assert_eq!(res, Err(ContractError::ContractsNotRegistered {}));
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        register_contracts_msg(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        register_contracts_msg(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_contracts"),
+            attr("overseer_contract", "overseer"),
+            attr("interest_model", "interest"),
+            attr("distribution_model", "distribution"),
+            attr("collector_contract", "collector"),
+            attr("distributor_contract", "distributor"),
+        ]
+    );
+
+    let config = read_config(&deps.storage).unwrap();
+    assert_eq!(
+        config.overseer_contract,
+        deps.api.addr_canonicalize("overseer").unwrap()
+    );
+    assert_eq!(
+        config.distributor_contract,
+        deps.api.addr_canonicalize("distributor").unwrap()
+    );
+
+    // registration is one-shot
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        register_contracts_msg(),
+    );
+    match res {
+        Err(ContractError::ContractsAlreadyRegistered {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier.with_borrow_rate(&[
+        (&"interest".to_string(), &Decimal256::permille(1)),
+        (&"interest2".to_string(), &Decimal256::percent(1)),
+    ]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        max_borrow_factor: Some(Decimal256::percent(95)),
+        interest_model: Some("interest2".to_string()),
+        distribution_model: None,
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            max_borrow_factor: Some(Decimal256::percent(101)),
+            interest_model: None,
+            distribution_model: None,
+        },
+    );
+    match res {
+        Err(ContractError::InvalidMaxBorrowFactor { max_borrow_factor }) => {
+            assert_eq!(max_borrow_factor, Decimal256::percent(101))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_after_blocks(100),
+        mock_info("owner", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_config"),
+            attr("prev_max_borrow_factor", "1"),
+            attr("max_borrow_factor", "0.95"),
+            attr("prev_interest_model", "interest"),
+            attr("interest_model", "interest2"),
+        ]
+    );
+
+    // 100 blocks accrued with the previous 0.1% rate
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(110000u64));
+    assert_eq!(state.last_interest_updated, mock_env().block.height + 100);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.interest_model, "interest2");
+    assert_eq!(config_res.max_borrow_factor, Decimal256::percent(95));
+}
+
+#[test]
+fn ownership_transfer() {
+    let mut deps = mock_dependencies(&[]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "owner2".to_string(),
+            expires_in: 100,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Ownership(OwnershipError::Unauthorized {}))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "owner2".to_string(),
+            expires_in: MAX_PROPOSAL_TTL + 1,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Ownership(OwnershipError::ExpiryTooLong {
+            expires_in: MAX_PROPOSAL_TTL + 1,
+            max: MAX_PROPOSAL_TTL,
+        }))
+    );
+
+    // a typo can be dropped before it is claimed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "owner3".to_string(),
+            expires_in: 100,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RejectOwnershipProposal {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "reject_ownership_proposal")]
+    );
+    assert_eq!(read_ownership_proposal(&deps.storage).unwrap(), None);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RejectOwnershipProposal {},
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Ownership(
+            OwnershipError::ProposalNotFound {}
+        ))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "owner2".to_string(),
+            expires_in: 100,
+        },
+    )
+    .unwrap();
+    let expires_at = mock_env().block.time.seconds() + 100;
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "propose_new_owner"),
+            attr("new_owner", "owner2"),
+            attr("expires_at", expires_at.to_string()),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner3", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Ownership(OwnershipError::Unauthorized {}))
+    );
+
+    let mut expired_env = mock_env();
+    expired_env.block.time = expired_env.block.time.plus_seconds(101);
+    let res = execute(
+        deps.as_mut(),
+        expired_env,
+        mock_info("owner2", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Ownership(OwnershipError::ProposalExpired {
+            expires_at
+        }))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner2", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_ownership"),
+            attr("new_owner", "owner2"),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.owner_addr, "owner2");
+    assert_eq!(read_ownership_proposal(&deps.storage).unwrap(), None);
+}
+
+#[test]
+fn deposit_and_redeem_slippage() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: Some(Uint256::from(1000001u64)),
+        },
+    );
+    match res {
+        Err(ContractError::MinMintAmountNotMet {
+            min_mint_amount,
+            mint_amount,
+        }) => {
+            assert_eq!(min_mint_amount, Uint256::from(1000001u64));
+            assert_eq!(mint_amount, Uint256::from(1000000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: Some(Uint256::from(1000000u64)),
+        },
+    )
+    .unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+
+    // the tax is deducted before comparing with min_receive
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: Some(Uint256::from(1000000u64)),
+            allow_partial: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("at-uusd", &[]), msg);
+    match res {
+        Err(ContractError::MinReceiveNotMet {
+            denom,
+            min_receive,
+            receive_amount,
+        }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(min_receive, Uint256::from(1000000u64));
+            assert_eq!(receive_amount, Uint256::from(990099u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: Some(Uint256::from(990099u64)),
+            allow_partial: None,
+        })
+        .unwrap(),
+    });
+    execute(deps.as_mut(), mock_env(), mock_info("at-uusd", &[]), msg).unwrap();
+}
+
+#[test]
+fn partial_redeem_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+
+    // exchange_rate = (2000000 - 500000) / 2000000 = 0.75
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(500000u64);
+    state.prev_aterra_supply = Uint256::from(2000000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // 2500000 aterra is worth 1875000 uusd, only 1500000 is available
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(2500000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: None,
+            allow_partial: Some(true),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("at-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(2000000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1500000u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "redeem_stable"),
+            attr("redeemer", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("burn_amount", "2000000"),
+            attr("redeem_amount", "1500000"),
+            attr("filled_amount", "2000000"),
+            attr("unfilled_amount", "500000"),
+        ]
+    );
+    assert_eq!(
+        read_state(&deps.storage).unwrap().prev_aterra_supply,
+        Uint256::zero()
+    );
+
+    // nothing to fill once the liquidity is drained
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(2000000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: None,
+            allow_partial: Some(true),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("at-uusd", &[]), msg);
+    match res {
+        Err(ContractError::NoStableAvailable { available, .. }) => {
+            assert_eq!(available, Uint256::zero())
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn withdrawal_queue() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(200000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+
+    // exchange_rate = (200000 + 1800000) / 2000000 = 1
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(1800000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let queue_msg = |sender: &str, amount: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&Cw20HookMsg::QueueRedeem { recipient: None }).unwrap(),
+        })
+    };
+
+    // only the aterra contract can escrow
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        queue_msg("addr0000", 600000),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-uusd", &[]),
+        queue_msg("addr0000", 600000),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "queue_redeem"),
+            attr("id", "0"),
+            attr("owner", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("aterra_amount", "600000"),
+        ]
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-uusd", &[]),
+        queue_msg("addr0001", 800000),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WithdrawalRequest { id: 1, denom: None },
+    )
+    .unwrap();
+    let request: WithdrawalRequestResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        request,
+        WithdrawalRequestResponse {
+            id: 1,
+            owner: "addr0001".to_string(),
+            recipient: "addr0001".to_string(),
+            aterra_amount: Uint256::from(800000u64),
+            position: 1,
+            aterra_ahead: Uint256::from(600000u64),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CancelWithdrawal { id: 1, denom: None },
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a deposit of 500000 brings the liquidity to 700000;
+    // the first request is settled and the second one partially
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(700000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0002",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500000u128),
+            }],
+        ),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "addr0002".to_string(),
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(700000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(600000u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0001".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }]
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes[5..],
+        vec![
+            attr("settled_withdrawals", "1"),
+            attr("settled_aterra_amount", "700000"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WithdrawalRequests {
+            start_after: None,
+            limit: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let requests: WithdrawalRequestsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        requests,
+        WithdrawalRequestsResponse {
+            total_aterra: Uint256::from(700000u64),
+            requests: vec![WithdrawalRequestResponse {
+                id: 1,
+                owner: "addr0001".to_string(),
+                recipient: "addr0001".to_string(),
+                aterra_amount: Uint256::from(700000u64),
+                position: 0,
+                aterra_ahead: Uint256::zero(),
+            }],
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WithdrawalRequests {
+            start_after: Some(u64::MAX),
+            limit: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let requests: WithdrawalRequestsResponse = from_binary(&res).unwrap();
+    assert_eq!(requests.requests, vec![]);
+
+    // the remaining escrow is returned on cancel
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CancelWithdrawal { id: 1, denom: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(700000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CancelWithdrawal { id: 1, denom: None },
+    );
+    match res {
+        Err(ContractError::WithdrawalRequestNotFound { id }) => assert_eq!(id, 1),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn redeem_exact_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+
+    // exchange_rate = (2000000 + 500000) / 2000000 = 1.25
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(500000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let redeem_msg = |aterra_amount: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(aterra_amount),
+            msg: to_binary(&Cw20HookMsg::RedeemExactStable {
+                amount: Uint256::from(100000u64),
+                recipient: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // 100000 uusd after tax costs 101000 uusd, which is 80800 aterra
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-uusd", &[]),
+        redeem_msg(50000),
+    );
+    match res {
+        Err(ContractError::InsufficientRedeemAterra { required, provided }) => {
+            assert_eq!(required, Uint256::from(80800u64));
+            assert_eq!(provided, Uint256::from(50000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-uusd", &[]),
+        redeem_msg(100000),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(80800u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(19200u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "redeem_exact_stable"),
+            attr("redeemer", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("burn_amount", "80800"),
+            attr("refund_amount", "19200"),
+            attr("redeem_amount", "101000"),
+            attr("receive_amount", "100000"),
+        ]
+    );
+}
+
+#[test]
+fn simulate_deposit_and_redeem() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+
+    // exchange_rate = (2000000 + 500000) / 2000000 = 1.25
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(500000u64);
+    state.prev_aterra_supply = Uint256::from(2000000u64);
+    state.prev_exchange_rate = Decimal256::percent(125);
+    store_state(&mut deps.storage, &state).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateDeposit {
+            amount: Uint256::from(1250000u64),
+            block_height: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let res: SimulationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        SimulationResponse {
+            exchange_rate: Decimal256::percent(125),
+            output_amount: Uint256::from(1000000u64),
+            tax_amount: Uint256::from(12500u64),
+            sufficient_liquidity: true,
+        }
+    );
+
+    // 125000 uusd redeemed, 123762 received after tax
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateRedeem {
+            aterra_amount: Uint256::from(100000u64),
+            block_height: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let res: SimulationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        SimulationResponse {
+            exchange_rate: Decimal256::percent(125),
+            output_amount: Uint256::from(123762u64),
+            tax_amount: Uint256::from(1238u64),
+            sufficient_liquidity: true,
+        }
+    );
+
+    // 2500000 uusd exceeds the 2000000 uusd balance
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateRedeem {
+            aterra_amount: Uint256::from(2000000u64),
+            block_height: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let res: SimulationResponse = from_binary(&res).unwrap();
+    assert!(!res.sufficient_liquidity);
+
+    // one block of pending interest; exchange_rate = (2000000 + 505000) / 2000000
+    let env = mock_env_after_blocks(1);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::SimulateRedeem {
+            aterra_amount: Uint256::from(100000u64),
+            block_height: Some(env.block.height),
+            denom: None,
+        },
+    )
+    .unwrap();
+    let res: SimulationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        SimulationResponse {
+            exchange_rate: Decimal256::from_ratio(2505000u64, 2000000u64),
+            output_amount: Uint256::from(124009u64),
+            tax_amount: Uint256::from(1241u64),
+            sufficient_liquidity: true,
+        }
+    );
+
+    // the projection does not touch the stored state
+    assert_eq!(
+        read_state(&deps.storage).unwrap().total_liabilities,
+        Decimal256::from_uint256(500000u64)
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateDeposit {
+            amount: Uint256::from(1250000u64),
+            block_height: Some(env.block.height - 1000),
+            denom: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "block_height must bigger than last_interest_updated")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn multi_denom_markets() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    let add_market_msg = ExecuteMsg::AddMarket {
+        stable_denom: "ukrw".to_string(),
+        aterra_code_id: 124u64,
+        aterra_name: None,
+        aterra_symbol: None,
+    };
+    let initial_deposit = [Coin {
+        denom: "ukrw".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }];
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &initial_deposit),
+        add_market_msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        add_market_msg.clone(),
+    );
+    match res {
+        Err(ContractError::InitialFundsNotDeposited { denom, amount }) => {
+            assert_eq!(denom, "ukrw");
+            assert_eq!(amount, Uint128::from(INITIAL_DEPOSIT_AMOUNT));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddMarket {
+            stable_denom: "uusd".to_string(),
+            aterra_code_id: 124u64,
+            aterra_name: None,
+            aterra_symbol: None,
+        },
+    );
+    match res {
+        Err(ContractError::MarketAlreadyExists { denom }) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &initial_deposit),
+        add_market_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: None,
+                code_id: 124u64,
+                funds: vec![],
+                label: "".to_string(),
+                msg: to_binary(&TokenInstantiateMsg {
+                    name: "Anchor Terra KRW".to_string(),
+                    symbol: "aKRT".to_string(),
+                    decimals: 6u8,
+                    initial_balances: vec![Cw20Coin {
+                        address: MOCK_CONTRACT_ADDR.to_string(),
+                        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                    }],
+                    mint: Some(MinterResponse {
+                        minter: MOCK_CONTRACT_ADDR.to_string(),
+                        cap: None,
+                    }),
+                    marketing: None,
+                })
+                .unwrap(),
+            }),
+            INSTANTIATE_MARKET_ATERRA_REPLY_ID,
+        )]
+    );
+
+    let mut msg = aterra_instantiate_reply(Some(instantiate_response_data("at-ukrw")));
+    msg.id = INSTANTIATE_MARKET_ATERRA_REPLY_ID;
+    reply(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+
+    // the pending market is consumed by the reply
+    let res = reply(deps.as_mut(), mock_env(), msg);
+    match res {
+        Err(ContractError::Std(StdError::NotFound { .. })) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &initial_deposit),
+        add_market_msg,
+    );
+    match res {
+        Err(ContractError::MarketAlreadyExists { denom }) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the ukrw market has its own aterra and exchange rate
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 2000000u128),
+            },
+        ],
+    );
+    deps.querier.with_token_balances(&[(
+        &"at-ukrw".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(2000000u128),
+            }],
+        ),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-ukrw".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(2000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deposit_stable"),
+            attr("depositor", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("mint_amount", "2000000"),
+            attr("deposit_amount", "2000000"),
+            attr("stable_denom", "ukrw"),
+        ]
+    );
+
+    deps.querier.with_token_balances(&[(
+        &"at-ukrw".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(2000000u128)),
+        ],
+    )]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"ukrw".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Market {
+            denom: "ukrw".to_string(),
+        },
+    )
+    .unwrap();
+    let market: MarketResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        market,
+        MarketResponse {
+            stable_denom: "ukrw".to_string(),
+            aterra_contract: "at-ukrw".to_string(),
+            exchange_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 2000000u128),
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Markets {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let markets: MarketsResponse = from_binary(&res).unwrap();
+    assert_eq!(markets.markets, vec![market]);
+
+    // redeem through the ukrw aterra
+    let redeem_msg = |hook_msg: Cw20HookMsg| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000000u128),
+            msg: to_binary(&hook_msg).unwrap(),
+        })
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-ukrw", &[]),
+        redeem_msg(Cw20HookMsg::RedeemStable {
+            recipient: None,
+            min_receive: None,
+            allow_partial: None,
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-ukrw".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "ukrw".to_string(),
+                    amount: Uint128::from(990099u128),
+                }]
+            })),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-ukrw", &[]),
+        redeem_msg(Cw20HookMsg::RedeemExactStable {
+            amount: Uint256::from(1000u64),
+            recipient: None,
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "redeem_exact_stable"),
+            attr("redeemer", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("burn_amount", "1010"),
+            attr("refund_amount", "998990"),
+            attr("redeem_amount", "1010"),
+            attr("receive_amount", "1000"),
+            attr("stable_denom", "ukrw"),
+        ]
+    );
+
+    // the ukrw aterra is queued in the ukrw withdrawal queue
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-ukrw", &[]),
+        redeem_msg(Cw20HookMsg::QueueRedeem { recipient: None }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "queue_redeem"),
+            attr("id", "0"),
+            attr("owner", "addr0000"),
+            attr("recipient", "addr0000"),
+            attr("aterra_amount", "1000000"),
+            attr("stable_denom", "ukrw"),
+        ]
+    );
+
+    let requests_query = |denom: Option<String>| QueryMsg::WithdrawalRequests {
+        start_after: None,
+        limit: None,
+        denom,
+    };
+    let res = query(deps.as_ref(), mock_env(), requests_query(None)).unwrap();
+    let requests: WithdrawalRequestsResponse = from_binary(&res).unwrap();
+    assert!(requests.requests.is_empty());
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        requests_query(Some("ukrw".to_string())),
+    )
+    .unwrap();
+    let requests: WithdrawalRequestsResponse = from_binary(&res).unwrap();
+    assert_eq!(requests.total_aterra, Uint256::from(1000000u64));
+    assert_eq!(requests.requests.len(), 1);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        requests_query(Some("ueur".to_string())),
+    );
+    match res {
+        Err(StdError::NotFound { .. }) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-ueur", &[]),
+        redeem_msg(Cw20HookMsg::QueueRedeem { recipient: None }),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // markets can only be removed by the owner once their deposits are redeemed
+    let remove_market_msg = |stable_denom: &str| ExecuteMsg::RemoveMarket {
+        stable_denom: stable_denom.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        remove_market_msg("ukrw"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        remove_market_msg("uusd"),
+    );
+    match res {
+        Err(ContractError::PrimaryMarketNotRemovable { denom }) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        remove_market_msg("ukrw"),
+    );
+    match res {
+        Err(ContractError::MarketNotEmpty { denom }) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_token_balances(&[(
+        &"at-ukrw".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            },
+        ],
+    );
+
+    // the queued withdrawal has to be settled or cancelled first
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        remove_market_msg("ukrw"),
+    );
+    match res {
+        Err(ContractError::MarketNotEmpty { denom }) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CancelWithdrawal { id: 0, denom: None },
+    );
+    match res {
+        Err(ContractError::WithdrawalRequestNotFound { id }) => assert_eq!(id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CancelWithdrawal {
+            id: 0,
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-ukrw".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        remove_market_msg("ukrw"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(990099u128),
+            }]
+        }))]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        remove_market_msg("ukrw"),
+    );
+    match res {
+        Err(ContractError::MarketNotFound { denom }) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Markets {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let markets: MarketsResponse = from_binary(&res).unwrap();
+    assert!(markets.markets.is_empty());
+}
+
+#[test]
+fn added_market_borrow_and_epoch() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    let initial_deposit = [Coin {
+        denom: "ukrw".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }];
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &initial_deposit),
+        ExecuteMsg::AddMarket {
+            stable_denom: "ukrw".to_string(),
+            aterra_code_id: 124u64,
+            aterra_name: None,
+            aterra_symbol: None,
+        },
+    )
+    .unwrap();
+    let mut msg = aterra_instantiate_reply(Some(instantiate_response_data("at-ukrw")));
+    msg.id = INSTANTIATE_MARKET_ATERRA_REPLY_ID;
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 9000000u128),
+            },
+        ],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"at-uusd".to_string(),
+            &[
+                (
+                    &MOCK_CONTRACT_ADDR.to_string(),
+                    &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                ),
+                (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+            ],
+        ),
+        (
+            &"at-ukrw".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            )],
+        ),
+    ]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0001",
+            &[Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(9000000u128),
+            }],
+        ),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: None,
+        },
+    )
+    .unwrap();
+    deps.querier.with_token_balances(&[
+        (
+            &"at-uusd".to_string(),
+            &[
+                (
+                    &MOCK_CONTRACT_ADDR.to_string(),
+                    &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                ),
+                (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+            ],
+        ),
+        (
+            &"at-ukrw".to_string(),
+            &[
+                (
+                    &MOCK_CONTRACT_ADDR.to_string(),
+                    &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                ),
+                (&"addr0001".to_string(), &Uint128::from(9000000u128)),
+            ],
+        ),
+    ]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    let info = mock_info("addr0000", &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(500000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 9000000u128),
+            },
+        ],
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(1000u64),
+            to: None,
+            denom: Some("ueur".to_string()),
+        },
+    );
+    match res {
+        Err(ContractError::MarketNotFound { denom }) => assert_eq!(denom, "ueur"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // loans of added markets are valued in the primary denom by the oracle
+    deps.querier.with_oracle_price(&[(
+        &("ukrw".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(10), mock_env().block.time.seconds()),
+    )]);
+
+    // 500000 uusd + 6000000 ukrw * 0.1 exceeds the 1000000 uusd borrow limit
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(6000000u64),
+            to: None,
+            denom: Some("ukrw".to_string()),
+        },
+    );
+    match res {
+        Err(ContractError::BorrowExceedsLimit {
+            denom,
+            loan_amount,
+            borrow_limit,
+        }) => {
+            assert_eq!(denom, "uusd");
+            assert_eq!(loan_amount, Uint256::from(1100000u64));
+            assert_eq!(borrow_limit, Uint256::from(1000000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(5000000u64),
+            to: None,
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "borrow_stable"),
+            attr("borrower", "addr0000"),
+            attr("borrow_amount", "5000000"),
+            attr("stable_denom", "ukrw"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(5000000u128),
+            }]
+        }))]
+    );
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(5000000u128),
+            },
+        ],
+    );
+
+    // the primary market keeps its own liability
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(500000u64));
+
+    // ten blocks of interest on the ukrw loan
+    let env = mock_env_after_blocks(10);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: Some(env.block.height),
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(5500000u64));
+    assert_eq!(borrower_info.interest_index, Decimal256::percent(110));
+
+    // exchange_rate = (5000000 + 5500000) / 10000000; the excess over the
+    // zero target deposit rate goes to the reserves
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::State {
+            block_height: Some(env.block.height),
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(5500000u64)
+    );
+    assert_eq!(state.total_reserves, Decimal256::from_uint256(500000u64));
+    assert_eq!(state.global_interest_index, Decimal256::percent(110));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("overseer", &[]),
+        ExecuteMsg::ExecuteEpochOperations {
+            deposit_rate: Decimal256::zero(),
+            target_deposit_rate: Decimal256::zero(),
+            threshold_deposit_rate: Decimal256::zero(),
+            distributed_interest: Uint256::zero(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "collector".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(25000u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "collector".to_string(),
+                amount: vec![Coin {
+                    denom: "ukrw".to_string(),
+                    amount: Uint128::from(500000u128),
+                }]
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes[res.attributes.len() - 4..],
+        [
+            attr("market", "ukrw"),
+            attr("market_total_liabilities", "5500000"),
+            attr("market_prev_exchange_rate", "1"),
+            attr("market_total_reserves", "500000"),
+        ]
+    );
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 475000u128),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(10500000u128),
+            },
+        ],
+    );
+
+    // the ukrw loan is repaid with ukrw and the excess is refunded
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(6000000u128),
+            }],
+        ),
+        ExecuteMsg::RepayStable {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "5500000"),
+            attr("stable_denom", "ukrw"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(500000u128),
+            }]
+        }))]
+    );
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 475000u128),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(10000000u128),
+            },
+        ],
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: Some(env.block.height),
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::zero());
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::SimulateDeposit {
+            amount: Uint256::from(1000u64),
+            block_height: Some(env.block.height),
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    let res: SimulationResponse = from_binary(&res).unwrap();
+    assert_eq!(res.exchange_rate, Decimal256::one());
+    assert_eq!(res.output_amount, Uint256::from(1000u64));
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::SimulateRedeem {
+            aterra_amount: Uint256::from(1000000u64),
+            block_height: Some(env.block.height),
+            denom: Some("ukrw".to_string()),
+        },
+    )
+    .unwrap();
+    let res: SimulationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        SimulationResponse {
+            exchange_rate: Decimal256::one(),
+            output_amount: Uint256::from(1000000u64),
+            tax_amount: Uint256::zero(),
+            sufficient_liquidity: true,
+        }
+    );
+}
+
+#[test]
+fn funds_validation() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    let coin = |denom: &str| Coin {
+        denom: denom.to_string(),
+        amount: Uint128::from(1000000u128),
+    };
+
+    // exactly one denom can be deposited
+    let deposit_msg = ExecuteMsg::DepositStable {
+        recipient: None,
+        min_mint_amount: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        deposit_msg.clone(),
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::NoFunds {})) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin("uusd"), coin("ukrw")]),
+        deposit_msg,
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::MultipleDenoms { count })) => assert_eq!(count, 2),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // repay accepts a single denom of a market
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin("uusd"), coin("ukrw")]),
+        ExecuteMsg::RepayStable {},
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::MultipleDenoms { count })) => assert_eq!(count, 2),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin("ukrw")]),
+        ExecuteMsg::RepayStable {},
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::DenomNotAllowed { denom })) => {
+            assert_eq!(denom, "ukrw")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the initial market deposit accepts only its own denom
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[coin("uusd"), coin("ukrw")]),
+        ExecuteMsg::AddMarket {
+            stable_denom: "ukrw".to_string(),
+            aterra_code_id: 124u64,
+            aterra_name: None,
+            aterra_symbol: None,
+        },
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::DenomNotAllowed { denom })) => {
+            assert_eq!(denom, "uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the other messages do not accept funds
+    let msgs = vec![
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(100u64),
+            to: None,
+            denom: None,
+        },
+        ExecuteMsg::ClaimRewards { to: None },
+        ExecuteMsg::CancelWithdrawal { id: 0, denom: None },
+        register_contracts_msg(),
+        ExecuteMsg::UpdateConfig {
+            max_borrow_factor: None,
+            interest_model: None,
+            distribution_model: None,
+        },
+        ExecuteMsg::ClaimOwnership {},
+        ExecuteMsg::RemoveMarket {
+            stable_denom: "ukrw".to_string(),
+        },
+    ];
+    for msg in msgs {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[coin("uusd")]),
+            msg,
+        );
+        match res {
+            Err(ContractError::Funds(FundsError::UnexpectedFunds {})) => (),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+}
+
+#[test]
+fn aterra_token_metadata() {
+    let deps = mock_dependencies(&[]);
+    let ibc_denom = format!("ibc/{}", "0123456789ABCDEF".repeat(4));
+
+    // native micro denoms derive the name and symbol
+    assert_eq!(
+        aterra_metadata(&deps.api, "usdr", None, None).unwrap(),
+        ("Anchor Terra SDR".to_string(), "aSDT".to_string())
+    );
+    assert_eq!(
+        aterra_metadata(
+            &deps.api,
+            "uusd",
+            Some("Anchor UST".to_string()),
+            Some("aUSD".to_string())
+        )
+        .unwrap(),
+        ("Anchor UST".to_string(), "aUSD".to_string())
+    );
+
+    // ibc and cw20 stables need explicit values
+    for denom in [ibc_denom.as_str(), "cw20:stable-token", "stake1"] {
+        match aterra_metadata(&deps.api, denom, None, None) {
+            Err(ContractError::MissingAterraMetadata { denom: d }) => assert_eq!(d, denom),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+        assert_eq!(
+            aterra_metadata(
+                &deps.api,
+                denom,
+                Some("Anchor Stable".to_string()),
+                Some("aSTB".to_string())
+            )
+            .unwrap(),
+            ("Anchor Stable".to_string(), "aSTB".to_string())
+        );
+    }
+
+    for denom in ["u", "", "1usd", "ibc/0123", "ibc/", "cw20:", "uusd!"] {
+        match aterra_metadata(&deps.api, denom, None, None) {
+            Err(ContractError::InvalidStableDenom { denom: d }) => assert_eq!(d, denom),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    match aterra_metadata(&deps.api, "uusd", Some("AT".to_string()), None) {
+        Err(ContractError::InvalidAterraName { name }) => assert_eq!(name, "AT"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    match aterra_metadata(&deps.api, "uusd", None, Some("aUST2".to_string())) {
+        Err(ContractError::InvalidAterraSymbol { symbol }) => assert_eq!(symbol, "aUST2"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // short denoms fail the instantiation instead of panicking
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = instantiate_msg();
+    msg.stable_denom = "u".to_string();
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "u".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    match instantiate(deps.as_mut(), mock_env(), info, msg) {
+        Err(ContractError::InvalidStableDenom { denom }) => assert_eq!(denom, "u"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn cw20_stable_market() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    let add_market_msg = ExecuteMsg::AddMarket {
+        stable_denom: "cw20:stable-token".to_string(),
+        aterra_code_id: 124u64,
+        aterra_name: Some("Anchor Stable Token".to_string()),
+        aterra_symbol: Some("aSTT".to_string()),
+    };
+
+    // the initial deposit is pulled from the owner instead
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "owner",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            }],
+        ),
+        add_market_msg.clone(),
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::UnexpectedFunds {})) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        add_market_msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "stable-token".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: "owner".to_string(),
+                recipient: MOCK_CONTRACT_ADDR.to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            })
+            .unwrap(),
+        }))
+    );
+    assert_eq!(res.messages[1].id, INSTANTIATE_MARKET_ATERRA_REPLY_ID);
+
+    let mut msg = aterra_instantiate_reply(Some(instantiate_response_data("at-stable")));
+    msg.id = INSTANTIATE_MARKET_ATERRA_REPLY_ID;
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &"stable-token".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 2000000u128),
+            )],
+        ),
+        (
+            &"at-stable".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            )],
+        ),
+    ]);
+
+    // cw20 stables cannot be sent as native coins
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "cw20:stable-token".to_string(),
+                amount: Uint128::from(2000000u128),
+            }],
+        ),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: None,
+        },
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::DenomNotAllowed { denom })) => {
+            assert_eq!(denom, "cw20:stable-token")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // deposits of an unknown token have no market
+    let deposit_hook = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(2000000u128),
+        msg: to_binary(&Cw20HookMsg::DepositStable {
+            recipient: None,
+            min_mint_amount: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("other-token", &[]),
+        deposit_hook.clone(),
+    );
+    match res {
+        Err(ContractError::MarketNotFound { denom }) => assert_eq!(denom, "cw20:other-token"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        deposit_hook,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-stable".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(2000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert_eq!(res.attributes[5], attr("stable_denom", "cw20:stable-token"));
+
+    deps.querier.with_token_balances(&[
+        (
+            &"stable-token".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 2000000u128),
+            )],
+        ),
+        (
+            &"at-stable".to_string(),
+            &[
+                (
+                    &MOCK_CONTRACT_ADDR.to_string(),
+                    &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                ),
+                (&"addr0000".to_string(), &Uint128::from(2000000u128)),
+            ],
+        ),
+    ]);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Market {
+            denom: "cw20:stable-token".to_string(),
+        },
+    )
+    .unwrap();
+    let market_res: MarketResponse = from_binary(&res).unwrap();
+    assert_eq!(market_res.exchange_rate, Decimal256::one());
+    assert_eq!(
+        market_res.aterra_supply,
+        Uint256::from(INITIAL_DEPOSIT_AMOUNT + 2000000u128)
+    );
+
+    // redeems are paid with an untaxed cw20 transfer
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("at-stable", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000000u128),
+            msg: to_binary(&Cw20HookMsg::RedeemStable {
+                recipient: None,
+                min_receive: Some(Uint256::from(1000000u128)),
+                allow_partial: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "at-stable".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "stable-token".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+}
+
+#[test]
+fn cw20_primary_market() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mut msg = instantiate_msg();
+    msg.stable_denom = "cw20:stable-token".to_string();
+    msg.aterra_name = Some("Anchor Stable Token".to_string());
+    msg.aterra_symbol = Some("aSTT".to_string());
+
+    // the initial deposit comes later, with the InitialDeposit hook
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            }],
+        ),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::UnexpectedFunds {})) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        aterra_instantiate_reply(Some(instantiate_response_data("at-stable"))),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        register_contracts_msg(),
+    )
+    .unwrap();
+
+    deps.querier.with_token_balances(&[
+        (
+            &"stable-token".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            )],
+        ),
+        (
+            &"at-stable".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            )],
+        ),
+    ]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    let hook = |sender: &str, amount: u128, msg: Cw20HookMsg| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&msg).unwrap(),
+        })
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "owner".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            msg: to_binary(&"initial_deposit").unwrap(),
+        }),
+    );
+    assert_eq!(res, Err(ContractError::InvalidHookMsg {}));
+
+    // the market is closed until the initial deposit
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook(
+            "addr0000",
+            1000000,
+            Cw20HookMsg::DepositStable {
+                recipient: None,
+                min_mint_amount: None,
+            },
+        ),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::MarketNotOpen {
+            denom: "cw20:stable-token".to_string()
+        })
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(1000u64),
+            to: None,
+            denom: None,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::MarketNotOpen {
+            denom: "cw20:stable-token".to_string()
+        })
+    );
+
+    // only the owner funds it, with the stable token and the exact amount
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook(
+            "addr0000",
+            INITIAL_DEPOSIT_AMOUNT,
+            Cw20HookMsg::InitialDeposit {},
+        ),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("other-token", &[]),
+        hook(
+            "owner",
+            INITIAL_DEPOSIT_AMOUNT,
+            Cw20HookMsg::InitialDeposit {},
+        ),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook("owner", 1000, Cw20HookMsg::InitialDeposit {}),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::InitialFundsNotDeposited {
+            denom: "cw20:stable-token".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        })
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook(
+            "owner",
+            INITIAL_DEPOSIT_AMOUNT,
+            Cw20HookMsg::InitialDeposit {},
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "initial_deposit"),
+            attr("stable_denom", "cw20:stable-token"),
+            attr("amount", INITIAL_DEPOSIT_AMOUNT.to_string()),
+        ]
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook(
+            "owner",
+            INITIAL_DEPOSIT_AMOUNT,
+            Cw20HookMsg::InitialDeposit {},
+        ),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::MarketAlreadyOpen {
+            denom: "cw20:stable-token".to_string()
+        })
+    );
+
+    // deposits, borrows and repays move the cw20 stable
+    deps.querier.with_token_balances(&[
+        (
+            &"stable-token".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+            )],
+        ),
+        (
+            &"at-stable".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            )],
+        ),
+    ]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook(
+            "addr0000",
+            1000000,
+            Cw20HookMsg::DepositStable {
+                recipient: None,
+                min_mint_amount: None,
+            },
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "at-stable".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"stable-token".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+            )],
+        ),
+        (
+            &"at-stable".to_string(),
+            &[
+                (
+                    &MOCK_CONTRACT_ADDR.to_string(),
+                    &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                ),
+                (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+            ],
+        ),
+    ]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(500000u64),
+            to: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "stable-token".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(500000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // native coins cannot repay a cw20 loan
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500000u128),
+            }],
+        ),
+        ExecuteMsg::RepayStable {},
+    );
+    match res {
+        Err(ContractError::Funds(FundsError::DenomNotAllowed { denom })) => {
+            assert_eq!(denom, "uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the excess of the repay is sent back
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stable-token", &[]),
+        hook("addr0000", 600000, Cw20HookMsg::RepayStable {}),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "stable-token".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(100000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "500000"),
+        ]
+    );
+}
+
+#[test]
+fn exchange_rate_history() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+    }]);
+
+    setup_market(&mut deps, instantiate_msg());
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0001".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::permille(1))]);
+    deps.querier.with_target_deposit_rate(Decimal256::one());
+    deps.querier.with_anc_emission_rate(Decimal256::one());
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::zero(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+
+    let env = mock_env_after_blocks(100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("overseer", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    // 1% of the deposits is distributed over a hundredth of a year
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1020000u128),
+        }],
+    );
+    let mut later_env = mock_env_after_blocks(200);
+    later_env.block.time = env.block.time.plus_seconds(SECONDS_PER_YEAR / 100);
+    execute(
+        deps.as_mut(),
+        later_env.clone(),
+        mock_info("overseer", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ExchangeRateHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history_res: ExchangeRateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history_res.snapshots,
+        vec![
+            ExchangeRateSnapshotResponse {
+                id: 0,
+                height: env.block.height,
+                time: env.block.time.seconds(),
+                exchange_rate: Decimal256::one(),
+                aterra_supply: Uint256::from(2000000u64),
+                total_liabilities: Decimal256::zero(),
+            },
+            ExchangeRateSnapshotResponse {
+                id: 1,
+                height: later_env.block.height,
+                time: later_env.block.time.seconds(),
+                exchange_rate: Decimal256::percent(101),
+                aterra_supply: Uint256::from(2000000u64),
+                total_liabilities: Decimal256::zero(),
+            },
+        ]
+    );
+    assert_eq!(
+        history_res.snapshots[0].annualized_yield(&history_res.snapshots[1]),
+        Some(Decimal256::one())
+    );
+    assert_eq!(
+        history_res.snapshots[1].annualized_yield(&history_res.snapshots[0]),
+        None
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ExchangeRateHistory {
+            start_after: Some(0),
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+    let history_res: ExchangeRateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history_res.snapshots.len(), 1);
+    assert_eq!(history_res.snapshots[0].id, 1);
+
+    // the oldest snapshots are dropped once the history is full
+    let state = read_state(&deps.storage).unwrap();
+    for _ in 0..MAX_EXCHANGE_RATE_SNAPSHOTS {
+        record_exchange_rate_snapshot(&mut deps.storage, &later_env, &state).unwrap();
+    }
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ExchangeRateHistory {
+            start_after: None,
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+    let history_res: ExchangeRateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history_res.snapshots[0].id, 2);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ExchangeRateHistory {
+            start_after: Some(MAX_EXCHANGE_RATE_SNAPSHOTS),
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history_res: ExchangeRateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history_res.snapshots.len(), 1);
+    assert_eq!(history_res.snapshots[0].id, MAX_EXCHANGE_RATE_SNAPSHOTS + 1);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ExchangeRateHistory {
+            start_after: Some(u64::MAX),
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history_res: ExchangeRateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history_res.snapshots, vec![]);
+}
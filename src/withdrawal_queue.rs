@@ -0,0 +1,240 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, Attribute, CosmosMsg, Deps, DepsMut, MessageInfo, Response, StdError,
+    StdResult, Uint128, WasmMsg,
+};
+
+use crate::error::ContractError;
+use crate::markets::{load_market, market_attributes, query_load_market};
+use crate::state::{
+    read_config, read_withdrawal_queue, read_withdrawal_queue_ahead, read_withdrawal_request,
+    read_withdrawal_requests, remove_withdrawal_request, store_withdrawal_queue,
+    store_withdrawal_request, Config, Market, State, WithdrawalQueue, WithdrawalRequest,
+};
+
+use cw20::Cw20ExecuteMsg;
+use moneymarket::market::{WithdrawalRequestResponse, WithdrawalRequestsResponse};
+
+/// Maximum number of queued requests settled by a single operation
+const MAX_SETTLEMENTS: u32 = 10;
+
+/// Escrow the received aterra until the market has enough liquidity
+pub fn queue_redeem(
+    deps: DepsMut,
+    market: &Market,
+    sender: Addr,
+    aterra_amount: Uint128,
+    recipient: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let recipient = recipient.unwrap_or_else(|| sender.clone());
+
+    if aterra_amount.is_zero() {
+        return Err(ContractError::ZeroQueueRedeem {});
+    }
+
+    let mut queue: WithdrawalQueue = read_withdrawal_queue(deps.storage, market)?;
+    let id = queue.next_id;
+    queue.next_id += 1;
+    queue.total_aterra += Uint256::from(aterra_amount);
+
+    store_withdrawal_request(
+        deps.storage,
+        market,
+        id,
+        &WithdrawalRequest {
+            owner: deps.api.addr_canonicalize(sender.as_str())?,
+            recipient: deps.api.addr_canonicalize(recipient.as_str())?,
+            aterra_amount: Uint256::from(aterra_amount),
+        },
+    )?;
+    store_withdrawal_queue(deps.storage, market, &queue)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "queue_redeem"),
+            attr("id", id.to_string()),
+            attr("owner", sender),
+            attr("recipient", recipient),
+            attr("aterra_amount", aterra_amount),
+        ])
+        .add_attributes(market_attributes(market)))
+}
+
+/// Drop a queued request and return its escrowed aterra to the owner
+pub fn cancel_withdrawal(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let market = load_market(deps.storage, &config, denom)?;
+    let request = read_withdrawal_request(deps.storage, &market, id)?
+        .ok_or(ContractError::WithdrawalRequestNotFound { id })?;
+
+    if deps.api.addr_canonicalize(info.sender.as_str())? != request.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut queue: WithdrawalQueue = read_withdrawal_queue(deps.storage, &market)?;
+    queue.total_aterra = queue.total_aterra - request.aterra_amount;
+
+    remove_withdrawal_request(deps.storage, &market, id);
+    store_withdrawal_queue(deps.storage, &market, &queue)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&market.aterra_contract)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: request.aterra_amount.into(),
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "cancel_withdrawal"),
+            attr("id", id.to_string()),
+            attr("aterra_amount", request.aterra_amount),
+        ])
+        .add_attributes(market_attributes(&market)))
+}
+
+/// Redeem queued requests in order with the given liquidity; the first
+/// request which cannot be fully served is partially filled and stops
+/// the settlement. The burned aterra is removed from `prev_aterra_supply`,
+/// so the caller has to store the state afterwards
+pub(crate) fn settle_withdrawal_queue(
+    deps: DepsMut,
+    market: &Market,
+    state: &mut State,
+    exchange_rate: Decimal256,
+    available: Uint256,
+) -> Result<(Vec<CosmosMsg>, Vec<Attribute>), ContractError> {
+    let mut queue: WithdrawalQueue = read_withdrawal_queue(deps.storage, market)?;
+    if queue.total_aterra.is_zero() || exchange_rate.is_zero() {
+        return Ok((vec![], vec![]));
+    }
+
+    let aterra_contract = deps.api.addr_humanize(&market.aterra_contract)?.to_string();
+    let asset = market.stable_asset();
+    let mut available = available;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut settled_count = 0u64;
+    let mut burn_total = Uint256::zero();
+
+    for (id, mut request) in
+        read_withdrawal_requests(deps.storage, market, None, Some(MAX_SETTLEMENTS))?
+    {
+        let burn_amount = std::cmp::min(request.aterra_amount, available / exchange_rate);
+        let redeem_amount = burn_amount * exchange_rate;
+        if redeem_amount.is_zero() {
+            break;
+        }
+
+        available = available - redeem_amount;
+        burn_total += burn_amount;
+        request.aterra_amount = request.aterra_amount - burn_amount;
+
+        messages.push(asset.transfer_msg(
+            &deps.api.addr_humanize(&request.recipient)?,
+            asset.deduct_tax(deps.as_ref(), redeem_amount)?,
+        )?);
+
+        if !request.aterra_amount.is_zero() {
+            store_withdrawal_request(deps.storage, market, id, &request)?;
+            break;
+        }
+
+        remove_withdrawal_request(deps.storage, market, id);
+        settled_count += 1;
+    }
+
+    if burn_total.is_zero() {
+        return Ok((vec![], vec![]));
+    }
+
+    queue.total_aterra = queue.total_aterra - burn_total;
+    store_withdrawal_queue(deps.storage, market, &queue)?;
+    state.prev_aterra_supply = state.prev_aterra_supply - burn_total;
+
+    messages.insert(
+        0,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: burn_total.into(),
+            })?,
+        }),
+    );
+
+    Ok((
+        messages,
+        vec![
+            attr("settled_withdrawals", settled_count.to_string()),
+            attr("settled_aterra_amount", burn_total),
+        ],
+    ))
+}
+
+pub fn query_withdrawal_request(
+    deps: Deps,
+    id: u64,
+    denom: Option<String>,
+) -> StdResult<WithdrawalRequestResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let request = read_withdrawal_request(deps.storage, &market, id)?
+        .ok_or_else(|| StdError::not_found("WithdrawalRequest"))?;
+    let (position, aterra_ahead) = read_withdrawal_queue_ahead(deps.storage, &market, id)?;
+
+    to_response(deps, id, request, position, aterra_ahead)
+}
+
+pub fn query_withdrawal_requests(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    denom: Option<String>,
+) -> StdResult<WithdrawalRequestsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = query_load_market(deps.storage, &config, denom)?;
+    let queue: WithdrawalQueue = read_withdrawal_queue(deps.storage, &market)?;
+    let requests = read_withdrawal_requests(deps.storage, &market, start_after, limit)?;
+
+    let (mut position, mut aterra_ahead) = match requests.first() {
+        Some((id, _)) => read_withdrawal_queue_ahead(deps.storage, &market, *id)?,
+        None => (0u64, Uint256::zero()),
+    };
+
+    let mut responses: Vec<WithdrawalRequestResponse> = vec![];
+    for (id, request) in requests {
+        let aterra_amount = request.aterra_amount;
+        responses.push(to_response(deps, id, request, position, aterra_ahead)?);
+
+        position += 1;
+        aterra_ahead += aterra_amount;
+    }
+
+    Ok(WithdrawalRequestsResponse {
+        total_aterra: queue.total_aterra,
+        requests: responses,
+    })
+}
+
+fn to_response(
+    deps: Deps,
+    id: u64,
+    request: WithdrawalRequest,
+    position: u64,
+    aterra_ahead: Uint256,
+) -> StdResult<WithdrawalRequestResponse> {
+    Ok(WithdrawalRequestResponse {
+        id,
+        owner: deps.api.addr_humanize(&request.owner)?.to_string(),
+        recipient: deps.api.addr_humanize(&request.recipient)?.to_string(),
+        aterra_amount: request.aterra_amount,
+        position,
+        aterra_ahead,
+    })
+}